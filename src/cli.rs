@@ -0,0 +1,537 @@
+use std::error::Error;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use dodge::{
+    Curve, Difficulty, Movement, Physics, Rules, HAZARD_PROBABILITY, INVULNERABLE_TICKS,
+    POWERUP_PROBABILITY, WAVE_PROBABILITY,
+};
+
+// Board used by arena mode and headless commands when no --size is given
+pub const DEFAULT_SIZE: (u16, u16) = (60, 20);
+
+// Replay speed multipliers cycled through with +/-; --speed stays within them
+pub const REPLAY_SPEEDS: [f64; 7] = [0.25, 0.5, 1.0, 2.0, 4.0, 8.0, 16.0];
+
+const USAGE: &str = "\
+Usage: dodge [COMMAND] [OPTIONS]
+
+Commands:
+  play      Play in the terminal (default)
+  replay    Watch a recorded replay
+  scores    List the high-score tables
+  bench     Measure simulation speed without a terminal
+  simulate  Run a game headlessly with a bot or a replay and print the result
+
+Options:
+  -h, --help     Print help (use `dodge <COMMAND> --help` for command options)
+  -V, --version  Print version";
+
+const GAME_OPTIONS: &str = "      --seed <N>                 RNG seed (random by default)
+      --size <WxH>               Board size
+      --config <PATH>            Config file (default: ~/.config/dodge/config.toml)
+      --curve <CURVE>            Difficulty curve: flat, linear, stepped or exponential
+      --tick-rate <MS>           Starting time between ticks in milliseconds
+      --spawn-probability <P>    Starting chance per column per tick of a new block
+      --movement <MODE>          horizontal, zone (bottom rows) or free (whole board)
+      --zone-rows <N>            Rows the player can roam in zone mode
+      --physics <MODE>           step (one cell per press) or inertia (speed up and slide)
+      --lives <N>                Hits before the run ends (default 1)
+      --shields <N>              Extra hits absorbed before lives are used
+      --powerup-probability <P>  Chance per tick of a power-up appearing (0 disables)
+      --hazard-probability <P>   Chance per tick of a telegraphed strike (0 disables)
+      --wave-probability <P>     Chance per tick of an authored wave (0 disables)
+      --spawns <MODE>            fair (random rows always leave a way out) or chaos";
+
+const PLAY_USAGE: &str = "\
+Usage: dodge [play] [OPTIONS]
+
+Options:
+      --mode <MODE>              classic (board follows the terminal) or arena
+                                 (fixed board, implied by --size)
+      --record <PATH>            Save a replay of the last round";
+
+const REPLAY_USAGE: &str = "\
+Usage: dodge replay [OPTIONS] <FILE>
+
+Keys: Space pause, Right/. step while paused, +/- change speed, q quit
+
+Options:
+      --speed <X>                Playback speed multiplier, 0.25 to 16 (default 1)
+      --config <PATH>            Config file for visuals";
+
+const SCORES_USAGE: &str = "\
+Usage: dodge scores [OPTIONS]
+
+Options:
+      --mode <MODE>              Only show tables for this mode
+      --size <WxH>               Only show tables for this board size";
+
+const BENCH_USAGE: &str = "\
+Usage: dodge bench [OPTIONS]
+
+Times simulation updates and frame rendering. The board defaults to filling
+a 300x100 terminal at the configured resolution.
+
+Options:
+      --ticks <N>                Ticks to simulate (default 100000)
+      --frames <N>               Frames to render (default 300)";
+
+const SIMULATE_USAGE: &str = "\
+Usage: dodge simulate [OPTIONS]
+
+Prints the outcome as key=value lines.
+
+Options:
+      --ticks <N>                Stop after this many ticks (default 10000)
+      --bot <BOT>                idle, random or dodge (default dodge)
+      --replay <PATH>            Re-simulate a replay instead of running a bot
+      --record <PATH>            Save a replay of the bot's run";
+
+// What the binary was asked to do
+#[derive(Debug)]
+pub enum Command {
+    Play(PlayOptions),
+    Replay(ReplayOptions),
+    Scores(ScoresOptions),
+    Bench(BenchOptions),
+    Simulate(SimulateOptions),
+    Help(String),
+    Version,
+}
+
+// Settings shared by every command that creates a game
+#[derive(Debug, Default)]
+pub struct GameOptions {
+    pub seed: Option<u64>,
+    pub size: Option<(u16, u16)>,
+    pub config: Option<PathBuf>,
+    // Overrides for values from the config file
+    pub curve: Option<Curve>,
+    pub tick_rate_ms: Option<u64>,
+    pub spawn_probability: Option<f64>,
+    pub movement: Option<Movement>,
+    pub zone_rows: Option<u16>,
+    pub physics: Option<Physics>,
+    pub lives: Option<u32>,
+    pub shields: Option<u32>,
+    pub powerup_probability: Option<f64>,
+    pub hazard_probability: Option<f64>,
+    pub wave_probability: Option<f64>,
+    pub fair: Option<bool>,
+}
+
+impl GameOptions {
+    // Consume a shared flag, returning false if it isn't one
+    fn parse_flag(&mut self, flag: &str, args: &mut Args) -> Result<bool, Box<dyn Error>> {
+        match flag {
+            "--seed" => self.seed = Some(args.value(flag)?),
+            "--size" => self.size = Some(parse_size(&args.value::<String>(flag)?)?),
+            "--config" => self.config = Some(args.value(flag)?),
+            "--curve" => self.curve = Some(parse_curve(&args.value::<String>(flag)?)?),
+            "--tick-rate" => self.tick_rate_ms = Some(args.value(flag)?),
+            "--spawn-probability" => self.spawn_probability = Some(args.value(flag)?),
+            "--movement" => self.movement = Some(parse_movement(&args.value::<String>(flag)?)?),
+            "--zone-rows" => self.zone_rows = Some(args.value(flag)?),
+            "--physics" => self.physics = Some(parse_physics(&args.value::<String>(flag)?)?),
+            "--lives" => self.lives = Some(args.value(flag)?),
+            "--shields" => self.shields = Some(args.value(flag)?),
+            "--powerup-probability" => self.powerup_probability = Some(args.value(flag)?),
+            "--hazard-probability" => self.hazard_probability = Some(args.value(flag)?),
+            "--wave-probability" => self.wave_probability = Some(args.value(flag)?),
+            "--spawns" => self.fair = Some(parse_spawn_mode(&args.value::<String>(flag)?)?),
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed.unwrap_or_else(rand::random)
+    }
+
+    pub fn size(&self) -> (u16, u16) {
+        self.size.unwrap_or(DEFAULT_SIZE)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BoardMode {
+    // The board follows the terminal size
+    #[default]
+    Classic,
+    // A fixed board centered in the terminal
+    Arena,
+}
+
+#[derive(Debug, Default)]
+pub struct PlayOptions {
+    pub game: GameOptions,
+    pub mode: BoardMode,
+    pub record: Option<PathBuf>,
+}
+
+impl PlayOptions {
+    // Fixed playfield size, if any
+    pub fn arena(&self) -> Option<(u16, u16)> {
+        match self.mode {
+            BoardMode::Classic => None,
+            BoardMode::Arena => Some(self.game.size()),
+        }
+    }
+
+    // High scores are kept separately for each mode
+    pub fn mode_name(&self, rules: &Rules) -> String {
+        let mut name = match self.mode {
+            BoardMode::Classic => "classic",
+            BoardMode::Arena => "arena",
+        }
+        .to_string();
+        let difficulty = &rules.difficulty;
+        let defaults = Difficulty::default();
+        let curve = difficulty.curve;
+        if curve != Curve::default() {
+            name = format!("{name}-{curve}");
+        }
+        // Tuned pacing and spawn rates change how hard a run is, so they
+        // are spelled out too
+        if difficulty.ramp != defaults.ramp {
+            name = format!("{name}-ramp{}", difficulty.ramp);
+        }
+        if (difficulty.base_tick_rate, difficulty.min_tick_rate)
+            != (defaults.base_tick_rate, defaults.min_tick_rate)
+        {
+            name = format!(
+                "{name}-tick{}-{}",
+                difficulty.base_tick_rate.as_millis(),
+                difficulty.min_tick_rate.as_millis()
+            );
+        }
+        if (
+            difficulty.base_spawn_probability,
+            difficulty.max_spawn_probability,
+        ) != (
+            defaults.base_spawn_probability,
+            defaults.max_spawn_probability,
+        ) {
+            name = format!(
+                "{name}-spawn{}-{}",
+                difficulty.base_spawn_probability, difficulty.max_spawn_probability
+            );
+        }
+        match rules.movement {
+            Movement::Horizontal => {}
+            Movement::Zone(rows) => name = format!("{name}-zone{rows}"),
+            Movement::Free => name = format!("{name}-free"),
+        }
+        if rules.physics != Physics::default() {
+            name = format!("{name}-{}", rules.physics);
+        }
+        if !rules.obstacles {
+            name = format!("{name}-plain");
+        }
+        for (label, probability, default) in [
+            ("powerups", rules.powerup_probability, POWERUP_PROBABILITY),
+            ("hazards", rules.hazard_probability, HAZARD_PROBABILITY),
+            ("waves", rules.wave_probability, WAVE_PROBABILITY),
+        ] {
+            if probability != default {
+                name = format!("{name}-{label}{probability}");
+            }
+        }
+        if !rules.fair {
+            name = format!("{name}-chaos");
+        }
+        // Extra lives make for an easier game, so they get their own table
+        if rules.lives != 1 || rules.shields != 0 {
+            name = format!("{name}-lives{}", rules.lives);
+            if rules.shields != 0 {
+                name = format!("{name}+{}", rules.shields);
+            }
+            if rules.invulnerable_ticks != INVULNERABLE_TICKS {
+                name = format!("{name}-immune{}", rules.invulnerable_ticks);
+            }
+        }
+        name
+    }
+}
+
+#[derive(Debug)]
+pub struct ReplayOptions {
+    pub path: PathBuf,
+    pub speed: f64,
+    pub config: Option<PathBuf>,
+}
+
+#[derive(Debug, Default)]
+pub struct ScoresOptions {
+    pub mode: Option<String>,
+    pub size: Option<(u16, u16)>,
+}
+
+#[derive(Debug)]
+pub struct BenchOptions {
+    pub game: GameOptions,
+    pub ticks: u64,
+    pub frames: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bot {
+    Idle,
+    Random,
+    Dodge,
+}
+
+#[derive(Debug)]
+pub struct SimulateOptions {
+    pub game: GameOptions,
+    pub ticks: u64,
+    pub bot: Bot,
+    pub replay: Option<PathBuf>,
+    pub record: Option<PathBuf>,
+}
+
+// Remaining command-line arguments, with a helper for flag values
+struct Args {
+    args: std::iter::Skip<std::vec::IntoIter<String>>,
+}
+
+impl Args {
+    fn next(&mut self) -> Option<String> {
+        self.args.next()
+    }
+
+    fn value<T: FromStr>(&mut self, flag: &str) -> Result<T, Box<dyn Error>> {
+        let value = self
+            .args
+            .next()
+            .ok_or_else(|| format!("{flag} requires a value"))?;
+        value
+            .parse()
+            .map_err(|_| format!("invalid value for {flag}: {value}").into())
+    }
+}
+
+impl Command {
+    pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<Self, Box<dyn Error>> {
+        let args: Vec<String> = args.into_iter().collect();
+        let (command, skip) = match args.first().map(String::as_str) {
+            Some(command @ ("play" | "replay" | "scores" | "bench" | "simulate" | "help")) => {
+                (command.to_string(), 1)
+            }
+            Some("-h" | "--help") => return Ok(Command::Help(USAGE.to_string())),
+            Some("-V" | "--version") => return Ok(Command::Version),
+            _ => ("play".to_string(), 0),
+        };
+
+        let usage = match command.as_str() {
+            "play" => format!("{PLAY_USAGE}\n{GAME_OPTIONS}"),
+            "replay" => REPLAY_USAGE.to_string(),
+            "scores" => SCORES_USAGE.to_string(),
+            "bench" => format!("{BENCH_USAGE}\n{GAME_OPTIONS}"),
+            "simulate" => format!("{SIMULATE_USAGE}\n{GAME_OPTIONS}"),
+            _ => USAGE.to_string(),
+        };
+        let rest = &args[skip..];
+        if command == "help" || rest.iter().any(|arg| arg == "-h" || arg == "--help") {
+            return Ok(Command::Help(usage));
+        }
+
+        let mut args = Args {
+            args: args.into_iter().skip(skip),
+        };
+        let result = match command.as_str() {
+            "play" => parse_play(&mut args).map(Command::Play),
+            "replay" => parse_replay(&mut args).map(Command::Replay),
+            "scores" => parse_scores(&mut args).map(Command::Scores),
+            "bench" => parse_bench(&mut args).map(Command::Bench),
+            _ => parse_simulate(&mut args).map(Command::Simulate),
+        };
+        result.map_err(|err| format!("{err}\n\n{usage}").into())
+    }
+}
+
+fn parse_play(args: &mut Args) -> Result<PlayOptions, Box<dyn Error>> {
+    let mut options = PlayOptions::default();
+    let mut mode = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--mode" => {
+                mode = Some(match args.value::<String>(&arg)?.as_str() {
+                    "classic" => BoardMode::Classic,
+                    "arena" => BoardMode::Arena,
+                    other => {
+                        return Err(
+                            format!("unknown mode `{other}` (expected classic or arena)").into(),
+                        )
+                    }
+                })
+            }
+            "--record" => options.record = Some(args.value(&arg)?),
+            flag if options.game.parse_flag(flag, args)? => {}
+            _ => return Err(format!("unknown argument: {arg}").into()),
+        }
+    }
+
+    // A board size only makes sense for a fixed arena
+    options.mode = match (mode, options.game.size) {
+        (Some(BoardMode::Classic), Some(_)) => {
+            return Err("--size cannot be used with --mode classic".into())
+        }
+        (Some(mode), _) => mode,
+        (None, Some(_)) => BoardMode::Arena,
+        (None, None) => BoardMode::Classic,
+    };
+    Ok(options)
+}
+
+fn parse_replay(args: &mut Args) -> Result<ReplayOptions, Box<dyn Error>> {
+    let mut path = None;
+    let mut speed: f64 = 1.0;
+    let mut config = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--speed" => {
+                speed = args.value(&arg)?;
+                let (min, max) = (REPLAY_SPEEDS[0], REPLAY_SPEEDS[REPLAY_SPEEDS.len() - 1]);
+                if !(min..=max).contains(&speed) {
+                    return Err(format!("--speed must be between {min} and {max}").into());
+                }
+            }
+            "--config" => config = Some(args.value(&arg)?),
+            _ if arg.starts_with("--") || path.is_some() => {
+                return Err(format!("unknown argument: {arg}").into())
+            }
+            _ => path = Some(PathBuf::from(arg)),
+        }
+    }
+    let path = path.ok_or("replay requires a file")?;
+    Ok(ReplayOptions {
+        path,
+        speed,
+        config,
+    })
+}
+
+fn parse_scores(args: &mut Args) -> Result<ScoresOptions, Box<dyn Error>> {
+    let mut options = ScoresOptions::default();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--mode" => options.mode = Some(args.value(&arg)?),
+            "--size" => options.size = Some(parse_size(&args.value::<String>(&arg)?)?),
+            _ => return Err(format!("unknown argument: {arg}").into()),
+        }
+    }
+    Ok(options)
+}
+
+fn parse_bench(args: &mut Args) -> Result<BenchOptions, Box<dyn Error>> {
+    let mut options = BenchOptions {
+        game: GameOptions::default(),
+        ticks: 100_000,
+        frames: 300,
+    };
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--ticks" => options.ticks = args.value(&arg)?,
+            "--frames" => options.frames = args.value(&arg)?,
+            flag if options.game.parse_flag(flag, args)? => {}
+            _ => return Err(format!("unknown argument: {arg}").into()),
+        }
+    }
+    Ok(options)
+}
+
+fn parse_simulate(args: &mut Args) -> Result<SimulateOptions, Box<dyn Error>> {
+    let mut options = SimulateOptions {
+        game: GameOptions::default(),
+        ticks: 10_000,
+        bot: Bot::Dodge,
+        replay: None,
+        record: None,
+    };
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--ticks" => options.ticks = args.value(&arg)?,
+            "--bot" => {
+                options.bot = match args.value::<String>(&arg)?.as_str() {
+                    "idle" => Bot::Idle,
+                    "random" => Bot::Random,
+                    "dodge" => Bot::Dodge,
+                    other => {
+                        return Err(format!(
+                            "unknown bot `{other}` (expected idle, random or dodge)"
+                        )
+                        .into())
+                    }
+                }
+            }
+            "--replay" => options.replay = Some(args.value(&arg)?),
+            "--record" => options.record = Some(args.value(&arg)?),
+            flag if options.game.parse_flag(flag, args)? => {}
+            _ => return Err(format!("unknown argument: {arg}").into()),
+        }
+    }
+    Ok(options)
+}
+
+fn parse_curve(value: &str) -> Result<Curve, Box<dyn Error>> {
+    Curve::from_name(value).ok_or_else(|| {
+        format!("unknown curve `{value}` (expected flat, linear, stepped or exponential)").into()
+    })
+}
+
+fn parse_movement(value: &str) -> Result<Movement, Box<dyn Error>> {
+    Movement::from_name(value).ok_or_else(|| {
+        format!("unknown movement `{value}` (expected horizontal, zone or free)").into()
+    })
+}
+
+fn parse_physics(value: &str) -> Result<Physics, Box<dyn Error>> {
+    Physics::from_name(value)
+        .ok_or_else(|| format!("unknown physics `{value}` (expected step or inertia)").into())
+}
+
+// Whether a spawn mode asks for fair spawns
+fn parse_spawn_mode(value: &str) -> Result<bool, Box<dyn Error>> {
+    match value {
+        "fair" => Ok(true),
+        "chaos" => Ok(false),
+        _ => Err(format!("unknown spawn mode `{value}` (expected fair or chaos)").into()),
+    }
+}
+
+// Parse a board size written as WIDTHxHEIGHT
+fn parse_size(value: &str) -> Result<(u16, u16), Box<dyn Error>> {
+    let invalid = || format!("invalid size `{value}` (expected WIDTHxHEIGHT)");
+    let (width, height) = value.split_once('x').ok_or_else(invalid)?;
+    let width: u16 = width.parse().map_err(|_| invalid())?;
+    let height: u16 = height.parse().map_err(|_| invalid())?;
+    if width == 0 || height < 2 {
+        return Err(format!("size `{value}` is too small").into());
+    }
+    Ok((width, height))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_rules_use_the_plain_table() {
+        let options = PlayOptions::default();
+        assert_eq!(options.mode_name(&Rules::default()), "classic");
+    }
+
+    #[test]
+    fn tuned_rules_get_their_own_table() {
+        let options = PlayOptions::default();
+        let mut rules = Rules::default();
+        rules.difficulty.base_spawn_probability = 0.01;
+        rules.powerup_probability = 0.5;
+        assert_eq!(
+            options.mode_name(&rules),
+            "classic-spawn0.01-0.3-powerups0.5"
+        );
+    }
+}
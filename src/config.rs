@@ -0,0 +1,558 @@
+use std::env;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use ratatui::style::{Color, Style};
+use serde::{de, Deserialize, Deserializer};
+
+use crate::controls::{Action, Key, Keymap, Mouse, Preset};
+use dodge::{
+    Curve, Difficulty, Game, Movement, Pattern, Physics, Resolution, Rules, DEFAULT_ZONE_ROWS,
+    HAZARD_PROBABILITY, INVULNERABLE_TICKS, POWERUP_PROBABILITY, WAVE_PROBABILITY,
+};
+
+// Settings loaded from config.toml; every field is optional and falls back to
+// the built-in defaults
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub gameplay: Gameplay,
+    pub visuals: Visuals,
+    pub controls: Controls,
+    #[serde(skip)]
+    pub path: Option<PathBuf>, // where the file is or would be, for saving
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Gameplay {
+    pub tick_rate_ms: u64,
+    pub min_tick_rate_ms: u64,
+    pub spawn_probability: f64,
+    pub max_spawn_probability: f64,
+    #[serde(deserialize_with = "deserialize_curve")]
+    pub curve: Curve,
+    pub ramp: u64,
+    #[serde(deserialize_with = "deserialize_movement")]
+    pub movement: Movement,
+    pub zone_rows: u16,
+    #[serde(deserialize_with = "deserialize_physics")]
+    pub physics: Physics,
+    pub lives: u32,
+    pub shields: u32,
+    pub invulnerable_ticks: u64,
+    pub powerup_probability: f64,
+    pub obstacles: bool,
+    pub hazard_probability: f64,
+    pub wave_probability: f64,
+    pub wave_file: Option<PathBuf>, // replaces the built-in waves
+    #[serde(rename = "spawns", deserialize_with = "deserialize_spawns")]
+    pub fair: bool,
+    #[serde(skip)]
+    pub patterns: Option<Vec<Pattern>>, // loaded from `wave_file`
+}
+
+impl Default for Gameplay {
+    fn default() -> Self {
+        let difficulty = Difficulty::default();
+        Self {
+            tick_rate_ms: difficulty.base_tick_rate.as_millis() as u64,
+            min_tick_rate_ms: difficulty.min_tick_rate.as_millis() as u64,
+            spawn_probability: difficulty.base_spawn_probability,
+            max_spawn_probability: difficulty.max_spawn_probability,
+            curve: difficulty.curve,
+            ramp: difficulty.ramp,
+            movement: Movement::default(),
+            zone_rows: DEFAULT_ZONE_ROWS,
+            physics: Physics::default(),
+            lives: 1,
+            shields: 0,
+            invulnerable_ticks: INVULNERABLE_TICKS,
+            powerup_probability: POWERUP_PROBABILITY,
+            obstacles: true,
+            hazard_probability: HAZARD_PROBABILITY,
+            wave_probability: WAVE_PROBABILITY,
+            wave_file: None,
+            fair: true,
+            patterns: None,
+        }
+    }
+}
+
+impl Gameplay {
+    pub fn difficulty(&self) -> Difficulty {
+        Difficulty {
+            curve: self.curve,
+            ramp: self.ramp,
+            base_tick_rate: Duration::from_millis(self.tick_rate_ms),
+            min_tick_rate: Duration::from_millis(self.min_tick_rate_ms),
+            base_spawn_probability: self.spawn_probability,
+            max_spawn_probability: self.max_spawn_probability,
+        }
+    }
+
+    pub fn rules(&self) -> Rules {
+        let movement = match self.movement {
+            Movement::Zone(_) => Movement::Zone(self.zone_rows),
+            movement => movement,
+        };
+        Rules {
+            difficulty: self.difficulty(),
+            movement,
+            physics: self.physics,
+            lives: self.lives,
+            shields: self.shields,
+            invulnerable_ticks: self.invulnerable_ticks,
+            powerup_probability: self.powerup_probability,
+            obstacles: self.obstacles,
+            hazard_probability: self.hazard_probability,
+            wave_probability: self.wave_probability,
+            fair: self.fair,
+        }
+    }
+
+    // A game using these settings
+    pub fn new_game(&self, width: u16, height: u16, seed: u64) -> Game {
+        let game = Game::new(width, height, seed).with_rules(self.rules());
+        match &self.patterns {
+            Some(patterns) => game.with_patterns(patterns.clone()),
+            None => game,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Visuals {
+    #[serde(deserialize_with = "deserialize_resolution")]
+    pub resolution: Resolution,
+    pub interpolate: bool, // draw fast movement between ticks
+    #[serde(deserialize_with = "deserialize_glyph")]
+    pub player_glyph: char,
+    #[serde(deserialize_with = "deserialize_glyph")]
+    pub block_glyph: char,
+    #[serde(deserialize_with = "deserialize_color")]
+    pub player_fg: Color,
+    #[serde(deserialize_with = "deserialize_color")]
+    pub player_bg: Color,
+    #[serde(deserialize_with = "deserialize_color")]
+    pub block_fg: Color,
+    #[serde(deserialize_with = "deserialize_color")]
+    pub block_bg: Color,
+}
+
+impl Default for Visuals {
+    fn default() -> Self {
+        Self {
+            resolution: Resolution::default(),
+            interpolate: true,
+            player_glyph: '@',
+            block_glyph: '#',
+            player_fg: Color::Black,
+            player_bg: Color::Yellow,
+            block_fg: Color::Reset,
+            block_bg: Color::Reset,
+        }
+    }
+}
+
+impl Visuals {
+    pub fn player_style(&self) -> Style {
+        Style::default().fg(self.player_fg).bg(self.player_bg)
+    }
+
+    pub fn block_style(&self) -> Style {
+        Style::default().fg(self.block_fg).bg(self.block_bg)
+    }
+}
+
+// Key bindings: a preset, with any action's keys replaced by a list of key
+// names
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Controls {
+    #[serde(deserialize_with = "deserialize_preset")]
+    pub preset: Preset,
+    #[serde(deserialize_with = "deserialize_keys")]
+    pub move_left: Option<Vec<Key>>,
+    #[serde(deserialize_with = "deserialize_keys")]
+    pub move_right: Option<Vec<Key>>,
+    #[serde(deserialize_with = "deserialize_keys")]
+    pub move_up: Option<Vec<Key>>,
+    #[serde(deserialize_with = "deserialize_keys")]
+    pub move_down: Option<Vec<Key>>,
+    #[serde(deserialize_with = "deserialize_keys")]
+    pub dash: Option<Vec<Key>>,
+    #[serde(deserialize_with = "deserialize_keys")]
+    pub pause: Option<Vec<Key>>,
+    #[serde(deserialize_with = "deserialize_keys")]
+    pub quit: Option<Vec<Key>>,
+    #[serde(deserialize_with = "deserialize_keys")]
+    pub controls: Option<Vec<Key>>,
+    #[serde(deserialize_with = "deserialize_mouse")]
+    pub mouse: Mouse,
+}
+
+impl Controls {
+    // The keys set for each action, in Action::ALL order
+    fn overrides(&self) -> [(Action, Option<&Vec<Key>>); 8] {
+        [
+            (Action::MoveLeft, self.move_left.as_ref()),
+            (Action::MoveRight, self.move_right.as_ref()),
+            (Action::MoveUp, self.move_up.as_ref()),
+            (Action::MoveDown, self.move_down.as_ref()),
+            (Action::Dash, self.dash.as_ref()),
+            (Action::Pause, self.pause.as_ref()),
+            (Action::Quit, self.quit.as_ref()),
+            (Action::Controls, self.controls.as_ref()),
+        ]
+    }
+
+    // The preset's keymap with the listed actions rebound; keys listed for
+    // an action are taken away from the one the preset gave them to
+    pub fn keymap(&self) -> Keymap {
+        let mut keymap = self.preset.keymap();
+        for (action, keys) in self.overrides() {
+            if let Some(keys) = keys {
+                keymap.set(action, keys);
+            }
+        }
+        keymap
+    }
+}
+
+#[derive(Debug)]
+pub struct ConfigError {
+    path: PathBuf,
+    message: String,
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path.display(), self.message)
+    }
+}
+
+impl Error for ConfigError {}
+
+impl Config {
+    // $XDG_CONFIG_HOME/dodge/config.toml, falling back to ~/.config
+    pub fn default_path() -> Option<PathBuf> {
+        let config_home = env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .filter(|path| path.is_absolute())
+            .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".config")))?;
+        Some(config_home.join("dodge").join("config.toml"))
+    }
+
+    // Load an explicitly requested file, or the default one if it exists
+    pub fn load(path: Option<&Path>) -> Result<Self, ConfigError> {
+        let (path, required) = match path {
+            Some(path) => (path.to_path_buf(), true),
+            None => match Self::default_path() {
+                Some(path) => (path, false),
+                None => return Ok(Self::default()),
+            },
+        };
+        let defaults = || Self {
+            path: Some(path.clone()),
+            ..Self::default()
+        };
+
+        let error = |message: String| ConfigError {
+            path: path.clone(),
+            message,
+        };
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == io::ErrorKind::NotFound && !required => return Ok(defaults()),
+            Err(err) => return Err(error(err.to_string())),
+        };
+
+        let mut config: Config = toml::from_str(&contents).map_err(|err| error(err.to_string()))?;
+        config.validate().map_err(error)?;
+        config.path = Some(path.clone());
+
+        // A relative wave file is looked up next to the config file
+        if let Some(file) = &config.gameplay.wave_file {
+            let file = path.parent().map_or(file.clone(), |dir| dir.join(file));
+            let patterns = Pattern::load(&file)
+                .map_err(|err| error(format!("gameplay.wave_file: {}: {err}", file.display())))?;
+            config.gameplay.patterns = Some(patterns);
+        }
+        Ok(config)
+    }
+
+    // Range checks that the TOML types alone can't express; also run after
+    // command-line overrides are applied
+    pub fn validate(&self) -> Result<(), String> {
+        let g = &self.gameplay;
+        if g.tick_rate_ms == 0 {
+            return Err("gameplay.tick_rate_ms must be greater than 0".into());
+        }
+        if g.min_tick_rate_ms == 0 || g.min_tick_rate_ms > g.tick_rate_ms {
+            return Err(format!(
+                "gameplay.min_tick_rate_ms must be between 1 and tick_rate_ms ({}), got {}",
+                g.tick_rate_ms, g.min_tick_rate_ms
+            ));
+        }
+        for (name, value) in [
+            ("spawn_probability", g.spawn_probability),
+            ("max_spawn_probability", g.max_spawn_probability),
+            ("powerup_probability", g.powerup_probability),
+            ("hazard_probability", g.hazard_probability),
+            ("wave_probability", g.wave_probability),
+        ] {
+            if !(0.0..=1.0).contains(&value) {
+                return Err(format!(
+                    "gameplay.{name} must be between 0 and 1, got {value}"
+                ));
+            }
+        }
+        if g.max_spawn_probability < g.spawn_probability {
+            return Err(format!(
+                "gameplay.max_spawn_probability ({}) must not be below spawn_probability ({})",
+                g.max_spawn_probability, g.spawn_probability
+            ));
+        }
+        if g.ramp == 0 {
+            return Err("gameplay.ramp must be greater than 0".into());
+        }
+        if g.zone_rows == 0 {
+            return Err("gameplay.zone_rows must be greater than 0".into());
+        }
+        if g.lives == 0 {
+            return Err("gameplay.lives must be greater than 0".into());
+        }
+        // Each key may be listed under one action only
+        let overrides = self.controls.overrides();
+        for (i, &(action, keys)) in overrides.iter().enumerate() {
+            for key in keys.into_iter().flatten() {
+                if let Some((other, _)) = overrides[i + 1..]
+                    .iter()
+                    .find(|(_, keys)| keys.is_some_and(|keys| keys.contains(key)))
+                {
+                    return Err(format!(
+                        "controls: `{key}` is bound to both {action} and {other}"
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Write the keymap and mouse mode into the [controls] section of the
+    // config file, leaving the rest of the file as it was
+    pub fn save_controls(&self, keymap: &Keymap) -> io::Result<()> {
+        let path = self.path.as_deref().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                "cannot locate the config directory",
+            )
+        })?;
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(err) => return Err(err),
+        };
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut section = keymap.to_toml();
+        if self.controls.mouse != Mouse::Off {
+            section.push_str(&format!("mouse = \"{}\"\n", self.controls.mouse));
+        }
+        fs::write(path, replace_section(&contents, "controls", &section))
+    }
+}
+
+// Swap a table's lines for new ones, or add them at the end if the table
+// isn't there yet
+fn replace_section(contents: &str, name: &str, section: &str) -> String {
+    let lines: Vec<&str> = contents.lines().collect();
+    let header = format!("[{name}]");
+    let Some(start) = lines.iter().position(|line| line.trim() == header) else {
+        let mut out = contents.to_string();
+        if !out.is_empty() {
+            if !out.ends_with('\n') {
+                out.push('\n');
+            }
+            out.push('\n');
+        }
+        out.push_str(section);
+        return out;
+    };
+    let end = match lines[start + 1..]
+        .iter()
+        .position(|line| line.trim_start().starts_with('['))
+    {
+        // Comments right above the next table belong to it
+        Some(i) => {
+            let mut end = start + 1 + i;
+            while end > start + 1
+                && matches!(lines[end - 1].trim_start().chars().next(), None | Some('#'))
+            {
+                end -= 1;
+            }
+            end
+        }
+        None => lines.len(),
+    };
+
+    let mut out = String::new();
+    for line in &lines[..start] {
+        out.push_str(line);
+        out.push('\n');
+    }
+    out.push_str(section);
+    if end < lines.len() && !lines[end].trim().is_empty() {
+        out.push('\n');
+    }
+    for line in &lines[end..] {
+        out.push_str(line);
+        out.push('\n');
+    }
+    out
+}
+
+fn deserialize_curve<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Curve, D::Error> {
+    let name = String::deserialize(deserializer)?;
+    Curve::from_name(&name).ok_or_else(|| {
+        de::Error::custom(format!(
+            "unknown curve `{name}`, expected one of: flat, linear, stepped, exponential"
+        ))
+    })
+}
+
+fn deserialize_movement<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Movement, D::Error> {
+    let name = String::deserialize(deserializer)?;
+    Movement::from_name(&name).ok_or_else(|| {
+        de::Error::custom(format!(
+            "unknown movement `{name}`, expected one of: horizontal, zone, free"
+        ))
+    })
+}
+
+fn deserialize_physics<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Physics, D::Error> {
+    let name = String::deserialize(deserializer)?;
+    Physics::from_name(&name).ok_or_else(|| {
+        de::Error::custom(format!(
+            "unknown physics `{name}`, expected step or inertia"
+        ))
+    })
+}
+
+fn deserialize_spawns<'de, D: Deserializer<'de>>(deserializer: D) -> Result<bool, D::Error> {
+    let name = String::deserialize(deserializer)?;
+    match name.as_str() {
+        "fair" => Ok(true),
+        "chaos" => Ok(false),
+        _ => Err(de::Error::custom(format!(
+            "unknown spawn mode `{name}`, expected fair or chaos"
+        ))),
+    }
+}
+
+fn deserialize_resolution<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Resolution, D::Error> {
+    let name = String::deserialize(deserializer)?;
+    Resolution::from_name(&name).ok_or_else(|| {
+        de::Error::custom(format!(
+            "unknown resolution `{name}`, expected one of: ascii, half, braille"
+        ))
+    })
+}
+
+fn deserialize_preset<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Preset, D::Error> {
+    let name = String::deserialize(deserializer)?;
+    Preset::from_name(&name).ok_or_else(|| {
+        de::Error::custom(format!(
+            "unknown preset `{name}`, expected one of: classic, arrows, wasd, vim"
+        ))
+    })
+}
+
+fn deserialize_keys<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Vec<Key>>, D::Error> {
+    let names = Vec::<String>::deserialize(deserializer)?;
+    names
+        .iter()
+        .map(|name| {
+            Key::from_name(name).ok_or_else(|| {
+                de::Error::custom(format!(
+                    "unknown key `{name}`, expected a character, a key name like left, \
+                     space or f1, or either with a ctrl- prefix"
+                ))
+            })
+        })
+        .collect::<Result<_, _>>()
+        .map(Some)
+}
+
+fn deserialize_mouse<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Mouse, D::Error> {
+    let name = String::deserialize(deserializer)?;
+    Mouse::from_name(&name).ok_or_else(|| {
+        de::Error::custom(format!(
+            "unknown mouse mode `{name}`, expected one of: off, follow, click"
+        ))
+    })
+}
+
+fn deserialize_glyph<'de, D: Deserializer<'de>>(deserializer: D) -> Result<char, D::Error> {
+    let glyph = String::deserialize(deserializer)?;
+    let mut chars = glyph.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) if !c.is_control() => Ok(c),
+        _ => Err(de::Error::custom(format!(
+            "glyph must be a single printable character, got {glyph:?}"
+        ))),
+    }
+}
+
+fn deserialize_color<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Color, D::Error> {
+    let name = String::deserialize(deserializer)?;
+    parse_color(&name).ok_or_else(|| {
+        de::Error::custom(format!(
+            "unknown color `{name}`, expected a color name, a 0-255 index or #rrggbb"
+        ))
+    })
+}
+
+// Accepts terminal color names, 256-color indices and #rrggbb
+fn parse_color(name: &str) -> Option<Color> {
+    let lower = name
+        .trim()
+        .to_ascii_lowercase()
+        .replace(['-', '_', ' '], "");
+    let color = match lower.as_str() {
+        "reset" | "default" => Color::Reset,
+        "black" => Color::Black,
+        "red" => Color::Red,
+        "green" => Color::Green,
+        "yellow" => Color::Yellow,
+        "blue" => Color::Blue,
+        "magenta" => Color::Magenta,
+        "cyan" => Color::Cyan,
+        "gray" | "grey" => Color::Gray,
+        "darkgray" | "darkgrey" => Color::DarkGray,
+        "lightred" => Color::LightRed,
+        "lightgreen" => Color::LightGreen,
+        "lightyellow" => Color::LightYellow,
+        "lightblue" => Color::LightBlue,
+        "lightmagenta" => Color::LightMagenta,
+        "lightcyan" => Color::LightCyan,
+        "white" => Color::White,
+        hex if hex.starts_with('#') && hex.len() == 7 && hex.is_ascii() => {
+            let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+            Color::Rgb(channel(1)?, channel(3)?, channel(5)?)
+        }
+        index => Color::Indexed(index.parse().ok()?),
+    };
+    Some(color)
+}
@@ -0,0 +1,357 @@
+use std::fmt;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use dodge::Input;
+
+// Something the player can do with a key
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    MoveLeft,
+    MoveRight,
+    MoveUp,
+    MoveDown,
+    Dash,
+    Pause,
+    Quit,
+    Controls, // open the controls menu
+}
+
+impl Action {
+    pub const ALL: [Action; 8] = [
+        Action::MoveLeft,
+        Action::MoveRight,
+        Action::MoveUp,
+        Action::MoveDown,
+        Action::Dash,
+        Action::Pause,
+        Action::Quit,
+        Action::Controls,
+    ];
+
+    // The key in the [controls] config section
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::MoveLeft => "move_left",
+            Action::MoveRight => "move_right",
+            Action::MoveUp => "move_up",
+            Action::MoveDown => "move_down",
+            Action::Dash => "dash",
+            Action::Pause => "pause",
+            Action::Quit => "quit",
+            Action::Controls => "controls",
+        }
+    }
+
+    // How the controls menu lists it
+    pub fn label(self) -> &'static str {
+        match self {
+            Action::MoveLeft => "Move left",
+            Action::MoveRight => "Move right",
+            Action::MoveUp => "Move up",
+            Action::MoveDown => "Move down",
+            Action::Dash => "Dash",
+            Action::Pause => "Pause",
+            Action::Quit => "Quit",
+            Action::Controls => "Controls",
+        }
+    }
+
+    // The direction a movement action moves the player
+    pub fn direction(self) -> Option<Input> {
+        match self {
+            Action::MoveLeft => Some(Input::Left),
+            Action::MoveRight => Some(Input::Right),
+            Action::MoveUp => Some(Input::Up),
+            Action::MoveDown => Some(Input::Down),
+            Action::Dash | Action::Pause | Action::Quit | Action::Controls => None,
+        }
+    }
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+// A key as bound in a keymap: the key itself and whether Ctrl is held.
+// Letters are case-sensitive, so "A" means Shift-a.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Key {
+    code: KeyCode,
+    ctrl: bool,
+}
+
+// Names for the keys that aren't a single character
+const KEY_NAMES: [(&str, KeyCode); 15] = [
+    ("left", KeyCode::Left),
+    ("right", KeyCode::Right),
+    ("up", KeyCode::Up),
+    ("down", KeyCode::Down),
+    ("space", KeyCode::Char(' ')),
+    ("enter", KeyCode::Enter),
+    ("esc", KeyCode::Esc),
+    ("tab", KeyCode::Tab),
+    ("backspace", KeyCode::Backspace),
+    ("delete", KeyCode::Delete),
+    ("insert", KeyCode::Insert),
+    ("home", KeyCode::Home),
+    ("end", KeyCode::End),
+    ("pageup", KeyCode::PageUp),
+    ("pagedown", KeyCode::PageDown),
+];
+
+impl Key {
+    pub fn from_event(event: &KeyEvent) -> Self {
+        // Terminals reporting every key as an escape code send shifted
+        // letters lowercase with Shift held; others send them uppercase
+        let code = match event.code {
+            KeyCode::Char(c) if event.modifiers.contains(KeyModifiers::SHIFT) => {
+                KeyCode::Char(c.to_ascii_uppercase())
+            }
+            code => code,
+        };
+        Self {
+            code,
+            ctrl: event.modifiers.contains(KeyModifiers::CONTROL),
+        }
+    }
+
+    // Parses names like "left", "space", "x", "A", "f5" and "ctrl-x"
+    pub fn from_name(name: &str) -> Option<Self> {
+        let (ctrl, name) = match name.strip_prefix("ctrl-") {
+            Some(rest) if !rest.is_empty() => (true, rest),
+            _ => (false, name),
+        };
+        let mut chars = name.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) if !c.is_control() && c != ' ' => KeyCode::Char(c),
+            _ => match KEY_NAMES.iter().find(|(n, _)| *n == name) {
+                Some(&(_, code)) => code,
+                None => KeyCode::F(
+                    name.strip_prefix('f')?
+                        .parse()
+                        .ok()
+                        .filter(|n| (1..=12).contains(n))?,
+                ),
+            },
+        };
+        Some(Self { code, ctrl })
+    }
+
+    // Whether the key's name reads back as the same key, so it can be
+    // written to the config
+    pub fn has_name(self) -> bool {
+        Key::from_name(&self.to_string()) == Some(self)
+    }
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.ctrl {
+            f.write_str("ctrl-")?;
+        }
+        match KEY_NAMES.iter().find(|(_, code)| *code == self.code) {
+            Some((name, _)) => f.write_str(name),
+            None => match self.code {
+                KeyCode::Char(c) => write!(f, "{c}"),
+                KeyCode::F(n) => write!(f, "f{n}"),
+                code => write!(f, "{code:?}"),
+            },
+        }
+    }
+}
+
+// Starting points for a keymap
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Preset {
+    // Arrows, WASD and hjkl all move
+    #[default]
+    Classic,
+    Arrows,
+    Wasd,
+    Vim,
+}
+
+impl Preset {
+    pub const ALL: [Preset; 4] = [Preset::Classic, Preset::Arrows, Preset::Wasd, Preset::Vim];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Preset::Classic => "classic",
+            Preset::Arrows => "arrows",
+            Preset::Wasd => "wasd",
+            Preset::Vim => "vim",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|p| p.name() == name)
+    }
+
+    pub fn keymap(self) -> Keymap {
+        // Key names for each action, in Action::ALL order
+        let names: [&[&str]; 8] = match self {
+            Preset::Classic => [
+                &["left", "a", "h"],
+                &["right", "d", "l"],
+                &["up", "w", "k"],
+                &["down", "s", "j"],
+                &["x"],
+                &["p", "space"],
+                &["q", "esc"],
+                &["c"],
+            ],
+            Preset::Arrows => [
+                &["left"],
+                &["right"],
+                &["up"],
+                &["down"],
+                &["space"],
+                &["p"],
+                &["q", "esc"],
+                &["c"],
+            ],
+            Preset::Wasd => [
+                &["a"],
+                &["d"],
+                &["w"],
+                &["s"],
+                &["space"],
+                &["p"],
+                &["q", "esc"],
+                &["c"],
+            ],
+            Preset::Vim => [
+                &["h"],
+                &["l"],
+                &["k"],
+                &["j"],
+                &["space"],
+                &["p"],
+                &["q", "esc"],
+                &["c"],
+            ],
+        };
+        Keymap {
+            bindings: names
+                .iter()
+                .map(|keys| {
+                    keys.iter()
+                        .filter_map(|name| Key::from_name(name))
+                        .collect()
+                })
+                .collect(),
+        }
+    }
+}
+
+impl fmt::Display for Preset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+// What the mouse does, if anything
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Mouse {
+    #[default]
+    Off,
+    // The player walks after the pointer
+    Follow,
+    // Clicking sends the player to the cell clicked, dashing when they can
+    Click,
+}
+
+impl Mouse {
+    pub const ALL: [Mouse; 3] = [Mouse::Off, Mouse::Follow, Mouse::Click];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Mouse::Off => "off",
+            Mouse::Follow => "follow",
+            Mouse::Click => "click",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|m| m.name() == name)
+    }
+}
+
+impl fmt::Display for Mouse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+// The keys bound to each action. A key belongs to one action at most.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Keymap {
+    bindings: Vec<Vec<Key>>, // in Action::ALL order
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Preset::default().keymap()
+    }
+}
+
+impl Keymap {
+    pub fn keys(&self, action: Action) -> &[Key] {
+        &self.bindings[action as usize]
+    }
+
+    // Bind the keys to the action in place of its old ones, taking them
+    // away from any other action
+    pub fn set(&mut self, action: Action, keys: &[Key]) {
+        self.bindings[action as usize].clear();
+        for &key in keys {
+            self.bind(action, key);
+        }
+    }
+
+    // Add a key to the action, taking it away from any other action
+    pub fn bind(&mut self, action: Action, key: Key) {
+        for keys in &mut self.bindings {
+            keys.retain(|&k| k != key);
+        }
+        self.bindings[action as usize].push(key);
+    }
+
+    // The action a key event triggers. Ctrl-C, which raw mode delivers as a
+    // key press, always quits.
+    pub fn action(&self, event: &KeyEvent) -> Option<Action> {
+        let key = Key::from_event(event);
+        if key.ctrl && key.code == KeyCode::Char('c') {
+            return Some(Action::Quit);
+        }
+        Action::ALL
+            .into_iter()
+            .find(|&action| self.keys(action).contains(&key))
+    }
+
+    // The action's keys for on-screen hints, like "p/space"
+    pub fn describe(&self, action: Action) -> String {
+        let keys: Vec<String> = self.keys(action).iter().map(Key::to_string).collect();
+        if keys.is_empty() {
+            "(unbound)".into()
+        } else {
+            keys.join("/")
+        }
+    }
+
+    // The [controls] config section that recreates this keymap
+    pub fn to_toml(&self) -> String {
+        let mut section = String::from("[controls]\n");
+        for action in Action::ALL {
+            let keys = self
+                .keys(action)
+                .iter()
+                .map(|key| toml::Value::String(key.to_string()))
+                .collect();
+            section.push_str(&format!("{action} = {}\n", toml::Value::Array(keys)));
+        }
+        section
+    }
+}
@@ -0,0 +1,120 @@
+use std::fmt;
+use std::time::Duration;
+
+// Starting values, used unchanged by the flat curve
+pub const TICK_RATE: Duration = Duration::from_millis(200);
+pub const NEW_BLOCK_PROBABILITY: f64 = 0.1; // probability per column per tick
+
+// Levels shown to the player run from 1 to MAX_LEVEL
+pub const MAX_LEVEL: u32 = 10;
+
+/// How difficulty grows with score.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Curve {
+    // Never changes; the original fixed difficulty
+    Flat,
+    // Ramps evenly until `ramp` points
+    #[default]
+    Linear,
+    // Same ramp, but jumps in discrete levels
+    Stepped,
+    // Closes half the remaining gap every `ramp / 4` points
+    Exponential,
+}
+
+impl Curve {
+    pub const ALL: [Curve; 4] = [
+        Curve::Flat,
+        Curve::Linear,
+        Curve::Stepped,
+        Curve::Exponential,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Curve::Flat => "flat",
+            Curve::Linear => "linear",
+            Curve::Stepped => "stepped",
+            Curve::Exponential => "exponential",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|curve| curve.name() == name)
+    }
+}
+
+impl fmt::Display for Curve {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Maps the current score to tick rate and spawn density.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Difficulty {
+    pub curve: Curve,
+    pub ramp: u64, // score at which the curve reaches (or nears) its maximum
+    pub base_tick_rate: Duration,
+    pub min_tick_rate: Duration,
+    pub base_spawn_probability: f64,
+    pub max_spawn_probability: f64,
+}
+
+impl Default for Difficulty {
+    fn default() -> Self {
+        Self {
+            curve: Curve::default(),
+            ramp: 1000,
+            base_tick_rate: TICK_RATE,
+            min_tick_rate: Duration::from_millis(60),
+            base_spawn_probability: NEW_BLOCK_PROBABILITY,
+            max_spawn_probability: 0.3,
+        }
+    }
+}
+
+impl Difficulty {
+    pub fn with_curve(curve: Curve) -> Self {
+        Self {
+            curve,
+            ..Self::default()
+        }
+    }
+
+    // How far along the curve the given score is, from 0.0 to 1.0
+    pub fn intensity(&self, score: u64) -> f64 {
+        let progress = (score as f64 / self.ramp.max(1) as f64).min(1.0);
+        match self.curve {
+            Curve::Flat => 0.0,
+            Curve::Linear => progress,
+            Curve::Stepped => {
+                let steps = f64::from(MAX_LEVEL - 1);
+                (progress * steps).floor() / steps
+            }
+            Curve::Exponential => {
+                let half_life = (self.ramp as f64 / 4.0).max(1.0);
+                1.0 - 0.5f64.powf(score as f64 / half_life)
+            }
+        }
+    }
+
+    pub fn level(&self, score: u64) -> u32 {
+        let steps = f64::from(MAX_LEVEL - 1);
+        1 + (self.intensity(score) * steps + 1e-9).floor() as u32
+    }
+
+    pub fn tick_rate(&self, score: u64) -> Duration {
+        let t = self.intensity(score);
+        let base = self.base_tick_rate.as_secs_f64();
+        let min = self.min_tick_rate.as_secs_f64().min(base);
+        Duration::from_secs_f64(base - (base - min) * t)
+    }
+
+    pub fn spawn_probability(&self, score: u64) -> f64 {
+        let t = self.intensity(score);
+        let base = self.base_spawn_probability;
+        let max = self.max_spawn_probability.max(base);
+        (base + (max - base) * t).clamp(0.0, 1.0)
+    }
+}
@@ -0,0 +1,283 @@
+use std::collections::VecDeque;
+
+use crate::hazard::{Hazard, HazardKind};
+use crate::obstacle::FallingBlock;
+use crate::pattern::WAVE_REACH;
+
+// Longest a block is followed; only stalled blocks get anywhere near it
+const MAX_LOOKAHEAD: usize = 4096;
+
+// Times an unsurvivable row is rerolled before gaps are carved into it
+pub const MAX_REROLLS: u32 = 3;
+
+/// The cells blocks will sweep through in the player's rows on each upcoming
+/// update, slot 0 being the next one to run.
+#[derive(Debug, Clone)]
+pub struct Forecast {
+    width: u16,
+    top: u16, // first player row
+    rows: u16,
+    // One bitset per slot, each row starting on a fresh word so whole rows
+    // can be shifted at once
+    slots: VecDeque<Vec<u64>>,
+}
+
+impl Forecast {
+    // An empty forecast for a board of the given width and player rows
+    pub fn new(width: u16, (top, bottom): (u16, u16)) -> Self {
+        Self {
+            width,
+            top,
+            rows: bottom.saturating_sub(top) + 1,
+            slots: VecDeque::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.slots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.slots.is_empty()
+    }
+
+    // Drop the slot for the update that just ran
+    pub fn advance(&mut self) {
+        self.slots.pop_front();
+    }
+
+    // Follow a copy of the block until it has fallen past the player's rows
+    pub fn add(&mut self, block: &FallingBlock) {
+        let bottom = self.top + self.rows - 1;
+        let stride = self.stride();
+        let mut block = block.clone();
+        for slot in 0..MAX_LOOKAHEAD {
+            if block.y > bottom {
+                break;
+            }
+            // The whole box around the move is marked, which is never less
+            // safe than the exact path
+            let sweep = block.step(self.width);
+            let ((left, right), (from, to)) = (sweep.columns(), sweep.rows());
+            let (first, last) = (from.max(self.top), to.min(bottom));
+            if first > last {
+                continue;
+            }
+            let top = self.top;
+            let cells = self.slot_mut(slot);
+            for y in first..=last {
+                let row = usize::from(y - top) * stride;
+                for x in left..right {
+                    cells[row + usize::from(x / 64)] |= 1 << (x % 64);
+                }
+            }
+        }
+    }
+
+    // Mark the cells a hazard strikes, advancing a copy of it once per slot
+    // from `first_slot` on
+    pub fn add_hazard(&mut self, hazard: &Hazard, first_slot: usize) {
+        let (width, top, rows, stride) = (self.width, self.top, self.rows, self.stride());
+        let mut hazard = *hazard;
+        for slot in first_slot.. {
+            if !hazard.advance() {
+                break;
+            }
+            if !hazard.is_striking() {
+                continue;
+            }
+            let cells = self.slot_mut(slot);
+            match hazard.kind {
+                HazardKind::Column(x) if x < width => {
+                    for row in 0..usize::from(rows) {
+                        cells[row * stride + usize::from(x / 64)] |= 1 << (x % 64);
+                    }
+                }
+                HazardKind::Row(y) if (top..top + rows).contains(&y) => {
+                    let row = usize::from(y - top) * stride;
+                    for x in 0..width {
+                        cells[row + usize::from(x / 64)] |= 1 << (x % 64);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    // Fold another forecast for the same board into this one
+    pub fn merge(&mut self, other: &Forecast) {
+        for (slot, words) in other.slots.iter().enumerate() {
+            for (cell, word) in self.slot_mut(slot).iter_mut().zip(words) {
+                *cell |= word;
+            }
+        }
+    }
+
+    // Words per row
+    fn stride(&self) -> usize {
+        usize::from(self.width).div_ceil(64)
+    }
+
+    fn slot_mut(&mut self, slot: usize) -> &mut Vec<u64> {
+        let size = self.stride() * usize::from(self.rows);
+        while self.slots.len() <= slot {
+            self.slots.push_back(vec![0; size]);
+        }
+        &mut self.slots[slot]
+    }
+}
+
+// Whether a player standing at (x, y) right before an update can outlive
+// everything the forecasts predict, moving up to WAVE_REACH cells per tick
+// between updates. All forecasts must share a board and rows.
+pub fn survivable(player: (u16, u16), forecasts: &[&Forecast]) -> bool {
+    let Some(first) = forecasts.first() else {
+        return true;
+    };
+    let (width, rows, stride) = (first.width, usize::from(first.rows), first.stride());
+    let (x, row) = (player.0, usize::from(player.1.saturating_sub(first.top)));
+    let horizon = forecasts.iter().map(|f| f.len()).max().unwrap_or(0);
+    if x >= width || row >= rows || horizon == 0 {
+        return true;
+    }
+    let size = rows * stride;
+
+    // Cells on the board: every bit but the padding at the end of each row
+    let tail = match width % 64 {
+        0 => u64::MAX,
+        bits => (1 << bits) - 1,
+    };
+    let board: Vec<u64> = (0..size)
+        .map(|i| {
+            if i % stride == stride - 1 {
+                tail
+            } else {
+                u64::MAX
+            }
+        })
+        .collect();
+
+    // Every cell any forecast sweeps on a slot
+    let swept = |slot: usize| {
+        let mut cells = vec![0; size];
+        for forecast in forecasts {
+            if let Some(words) = forecast.slots.get(slot) {
+                for (cell, word) in cells.iter_mut().zip(words) {
+                    *cell |= word;
+                }
+            }
+        }
+        cells
+    };
+
+    // Cells the player could be in, relative to the first player row
+    let mut reachable = vec![0u64; size];
+    reachable[row * stride + usize::from(x / 64)] |= 1 << (x % 64);
+
+    let mut last_swept = Vec::new();
+    for slot in 0..horizon {
+        // Between updates the player can step into any neighbouring cell the
+        // last update didn't leave a block in
+        if slot > 0 {
+            for _ in 0..WAVE_REACH {
+                let mut next = reachable.clone();
+                for row in 0..rows {
+                    let start = row * stride;
+                    for i in start..start + stride {
+                        let word = reachable[i];
+                        let from_left = if i > start { reachable[i - 1] >> 63 } else { 0 };
+                        let from_right = if i + 1 < start + stride {
+                            reachable[i + 1] << 63
+                        } else {
+                            0
+                        };
+                        next[i] |= word << 1 | from_left | word >> 1 | from_right;
+                        if row > 0 {
+                            next[i] |= reachable[i - stride];
+                        }
+                        if row + 1 < rows {
+                            next[i] |= reachable[i + stride];
+                        }
+                    }
+                }
+                for ((cell, swept), board) in next.iter_mut().zip(&last_swept).zip(&board) {
+                    *cell &= !swept & board;
+                }
+                reachable = next;
+            }
+        }
+
+        // Then the update itself takes out every cell a block sweeps
+        let swept = swept(slot);
+        let mut alive = false;
+        for (cell, swept) in reachable.iter_mut().zip(&swept) {
+            *cell &= !swept;
+            alive |= *cell != 0;
+        }
+        if !alive {
+            return false;
+        }
+        last_swept = swept;
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A forecast for a single player row at y = 5 with a wall falling from
+    // y = 3, open only at `gap`. It reaches the player on the second update.
+    fn wall(width: u16, gap: u16) -> Forecast {
+        let mut forecast = Forecast::new(width, (5, 5));
+        for x in (0..width).filter(|&x| x != gap) {
+            forecast.add(&FallingBlock::new(x, 3));
+        }
+        forecast
+    }
+
+    #[test]
+    fn gap_within_reach_is_survivable() {
+        let forecast = wall(10, 4);
+        assert!(survivable((4, 5), &[&forecast]));
+        assert!(survivable((5, 5), &[&forecast]));
+        assert!(survivable((3, 5), &[&forecast]));
+    }
+
+    #[test]
+    fn gap_out_of_reach_is_not() {
+        let forecast = wall(10, 4);
+        assert!(!survivable((6, 5), &[&forecast]));
+        assert!(!survivable((0, 5), &[&forecast]));
+    }
+
+    #[test]
+    fn moves_carry_across_word_boundaries() {
+        for (gap, player) in [(64, 63), (63, 64), (128, 127), (127, 128)] {
+            assert!(survivable((player, 5), &[&wall(130, gap)]));
+        }
+        assert!(!survivable((62, 5), &[&wall(130, 64)]));
+        assert!(!survivable((66, 5), &[&wall(130, 64)]));
+    }
+
+    #[test]
+    fn padding_past_the_last_column_is_no_gap() {
+        // The only free bits are past the board's right edge
+        let forecast = wall(70, 70);
+        assert!(!survivable((69, 5), &[&forecast]));
+    }
+
+    #[test]
+    fn column_strike_between_blocks_is_unavoidable() {
+        let hazard = Hazard::new(HazardKind::Column(1));
+        let mut forecast = Forecast::new(3, (9, 9));
+        forecast.add_hazard(&hazard, 0);
+        assert!(survivable((1, 9), &[&forecast]));
+
+        // Blocks pass through the cells either side while it strikes
+        for x in [0, 2] {
+            forecast.add(&FallingBlock::new(x, 1));
+        }
+        assert!(!survivable((1, 9), &[&forecast]));
+    }
+}
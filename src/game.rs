@@ -0,0 +1,857 @@
+use std::cmp::Ordering;
+use std::sync::Arc;
+use std::time::Duration;
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use crate::fairness::{self, Forecast};
+use crate::grid::Grid;
+use crate::hazard::{Hazard, HazardKind};
+use crate::obstacle::{BlockKind, FallingBlock, MAX_SLAB_WIDTH};
+use crate::pattern::Pattern;
+use crate::physics::{self, Fixed, CELL};
+use crate::powerup::{Item, PowerUp};
+use crate::rules::{Physics, Rules};
+
+/// A player action fed into the simulation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Input {
+    Left,
+    Right,
+    Up,
+    Down,
+    // Several cells at once the way the player last moved
+    Dash,
+}
+
+// Cells a dash covers and ticks before the next one
+pub const DASH_CELLS: u16 = 3;
+pub const DASH_COOLDOWN: u64 = 20;
+
+impl Input {
+    pub fn name(self) -> &'static str {
+        match self {
+            Input::Left => "left",
+            Input::Right => "right",
+            Input::Up => "up",
+            Input::Down => "down",
+            Input::Dash => "dash",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "left" => Some(Input::Left),
+            "right" => Some(Input::Right),
+            "up" => Some(Input::Up),
+            "down" => Some(Input::Down),
+            "dash" => Some(Input::Dash),
+            _ => None,
+        }
+    }
+}
+
+// A wave being dropped onto the board, laid out for the current width
+#[derive(Debug, Clone)]
+struct Wave {
+    rows: Vec<Option<(u16, u16)>>, // open columns per row, None for blank rows
+    next: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct Game {
+    pub player_x: u16,
+    pub player_y: u16,
+    // With inertia: how far across their cell the player is, their speed
+    // and the direction pushed since the last update, along x and y
+    offset: [Fixed; 2],
+    velocity: [Fixed; 2],
+    thrust: [i8; 2],
+    facing: Option<Input>, // the direction last moved in, for dashes
+    dash_cooldown: u64,    // ticks until the player can dash again
+    blocks: Vec<FallingBlock>,
+    occupied: Grid, // cells covered by blocks, kept in sync with `blocks`
+    items: Vec<Item>,
+    hazards: Vec<Hazard>,
+    patterns: Arc<Vec<Pattern>>, // waves the spawner can choose from
+    wave: Option<Wave>,
+    forecast: Option<Forecast>, // upcoming block sweeps, kept in fair mode
+    effects: [u64; PowerUp::ALL.len()], // ticks left for each timed power-up
+    pub score: u64,
+    pub tick: u64,   // number of updates run so far
+    pub width: u16,  // playable width (inner area)
+    pub height: u16, // playable height (inner area)
+    pub seed: u64,
+    pub rules: Rules,
+    pub lives: u32,
+    pub shields: u32,
+    pub invulnerable: u64, // ticks left in which hits are ignored
+    pub game_over: bool,
+    rng: ChaCha8Rng,
+}
+
+impl Game {
+    // Identical seeds and inputs always produce identical runs
+    pub fn new(width: u16, height: u16, seed: u64) -> Self {
+        let mut game = Self {
+            player_x: width / 2,
+            player_y: height.saturating_sub(2),
+            offset: [CELL / 2; 2],
+            velocity: [0; 2],
+            thrust: [0; 2],
+            facing: None,
+            dash_cooldown: 0,
+            blocks: Vec::new(),
+            occupied: Grid::new(width, height),
+            items: Vec::new(),
+            hazards: Vec::new(),
+            patterns: Pattern::builtin(),
+            wave: None,
+            forecast: None,
+            effects: [0; PowerUp::ALL.len()],
+            score: 0,
+            tick: 0,
+            width,
+            height,
+            seed,
+            rules: Rules::default(),
+            lives: 1,
+            shields: 0,
+            invulnerable: 0,
+            game_over: false,
+            rng: ChaCha8Rng::seed_from_u64(seed),
+        };
+        game.rebuild_forecast();
+        game
+    }
+
+    // Switch to the given rules, moving the player into its allowed rows
+    pub fn with_rules(mut self, rules: Rules) -> Self {
+        self.rules = rules;
+        self.lives = rules.lives.max(1);
+        self.shields = rules.shields;
+        self.player_y = self.clamp_row(self.player_y);
+        self.velocity = [0; 2];
+        self.thrust = [0; 2];
+        self.rebuild_forecast();
+        self
+    }
+
+    // First and last row the player may occupy
+    pub fn player_rows(&self) -> (u16, u16) {
+        self.rules.movement.rows(self.height)
+    }
+
+    fn clamp_row(&self, y: u16) -> u16 {
+        let (top, bottom) = self.player_rows();
+        y.clamp(top, bottom)
+    }
+
+    pub fn blocks(&self) -> &[FallingBlock] {
+        &self.blocks
+    }
+
+    // Place a block directly, e.g. to set up a scenario in a test or tool
+    pub fn add_block(&mut self, block: FallingBlock) {
+        for x in block.x..block.x.saturating_add(block.width) {
+            self.occupied.set(x, block.y);
+        }
+        self.blocks.push(block);
+    }
+
+    pub fn items(&self) -> &[Item] {
+        &self.items
+    }
+
+    // Replace the built-in waves
+    pub fn with_patterns(mut self, patterns: Vec<Pattern>) -> Self {
+        self.patterns = Arc::new(patterns);
+        self
+    }
+
+    pub fn patterns(&self) -> &[Pattern] {
+        &self.patterns
+    }
+
+    pub fn hazards(&self) -> &[Hazard] {
+        &self.hazards
+    }
+
+    // Where blocks already on the board will sweep the player's rows, kept
+    // only in fair mode
+    pub fn forecast(&self) -> Option<&Forecast> {
+        self.forecast.as_ref()
+    }
+
+    // Ticks left on a timed power-up, 0 if it isn't active
+    pub fn effect(&self, kind: PowerUp) -> u64 {
+        self.effects[kind.index()]
+    }
+
+    // Timed power-ups currently active, with the ticks they have left
+    pub fn active_effects(&self) -> impl Iterator<Item = (PowerUp, u64)> + '_ {
+        PowerUp::ALL
+            .into_iter()
+            .map(|kind| (kind, self.effect(kind)))
+            .filter(|&(_, ticks)| ticks > 0)
+    }
+
+    pub fn level(&self) -> u32 {
+        self.rules.difficulty.level(self.score)
+    }
+
+    // How long the front-end should wait before the next update
+    pub fn tick_rate(&self) -> Duration {
+        let rate = self.rules.difficulty.tick_rate(self.score);
+        if self.effect(PowerUp::SlowTime) > 0 {
+            rate * 2
+        } else {
+            rate
+        }
+    }
+
+    fn apply_powerup(&mut self, kind: PowerUp) {
+        match kind {
+            PowerUp::Shield => self.shields += 1,
+            PowerUp::Bomb => {
+                self.blocks.clear();
+                self.occupied.clear();
+                self.rebuild_forecast();
+            }
+            // Collecting a timed power-up again restarts its timer
+            _ => self.effects[kind.index()] = kind.duration(),
+        }
+    }
+
+    // Apply and remove every item in the player's cell
+    fn collect_items(&mut self) {
+        let (x, y) = (self.player_x, self.player_y);
+        let mut collected = Vec::new();
+        self.items.retain(|item| {
+            let hit = item.x == x && item.y == y;
+            if hit {
+                collected.push(item.kind);
+            }
+            !hit
+        });
+        for kind in collected {
+            self.apply_powerup(kind);
+        }
+    }
+
+    // Take a hit from a block: a shield goes first, then a life, and the game
+    // ends with the last life. Surviving a hit grants a short invulnerability.
+    fn hit(&mut self) {
+        if self.invulnerable > 0 {
+            return;
+        }
+        if self.shields > 0 {
+            self.shields -= 1;
+        } else {
+            self.lives = self.lives.saturating_sub(1);
+            if self.lives == 0 {
+                self.game_over = true;
+                return;
+            }
+        }
+        self.invulnerable = self.rules.invulnerable_ticks;
+    }
+
+    // Apply a single player input. With inertia it pushes the player, who
+    // moves on the next update; otherwise they step straight away, taking a
+    // hit if they move into a block.
+    pub fn handle_input(&mut self, input: Input) {
+        if self.game_over {
+            return;
+        }
+
+        if input == Input::Dash {
+            self.dash();
+            return;
+        }
+        self.facing = Some(input);
+        match self.rules.physics {
+            Physics::Step => {
+                self.step_player(input);
+            }
+            Physics::Inertia => match input {
+                Input::Left => self.thrust[0] = -1,
+                Input::Right => self.thrust[0] = 1,
+                Input::Up => self.thrust[1] = -1,
+                Input::Down => self.thrust[1] = 1,
+                Input::Dash => {}
+            },
+        }
+    }
+
+    // Jump up to DASH_CELLS cells the way the player last moved, checking
+    // every cell on the way. A dash blocked from the start doesn't count.
+    fn dash(&mut self) {
+        let Some(facing) = self.facing else {
+            return;
+        };
+        if self.dash_cooldown > 0 {
+            return;
+        }
+        for _ in 0..DASH_CELLS {
+            if !self.step_player(facing) {
+                break;
+            }
+            self.dash_cooldown = DASH_COOLDOWN;
+            if self.game_over {
+                break;
+            }
+        }
+    }
+
+    // Ticks until the player can dash again
+    pub fn dash_cooldown(&self) -> u64 {
+        self.dash_cooldown
+    }
+
+    // Move the player one cell, returning false if they were already at the
+    // edge of the area they may use
+    fn step_player(&mut self, input: Input) -> bool {
+        let from = (self.player_x, self.player_y);
+        match input {
+            Input::Left => {
+                if self.player_x > 0 {
+                    self.player_x -= 1;
+                }
+            }
+            Input::Right => {
+                if self.player_x < self.width.saturating_sub(1) {
+                    self.player_x += 1;
+                }
+            }
+            // Rows outside the movement mode's range are never entered, so
+            // these do nothing in horizontal mode
+            Input::Up => self.player_y = self.clamp_row(self.player_y.saturating_sub(1)),
+            Input::Down => self.player_y = self.clamp_row(self.player_y + 1),
+            Input::Dash => {}
+        }
+
+        self.collect_items();
+        if self.check_collision() || self.in_strike() {
+            self.hit();
+        }
+        from != (self.player_x, self.player_y)
+    }
+
+    // Carry the player along one axis (0 for x, 1 for y) with their
+    // momentum, a cell at a time so no block is skipped. Friction slows them
+    // when no direction was pushed.
+    fn glide(&mut self, axis: usize) {
+        let thrust = std::mem::take(&mut self.thrust[axis]);
+        let (back, forward) = if axis == 0 {
+            (Input::Left, Input::Right)
+        } else {
+            (Input::Up, Input::Down)
+        };
+        let speed = physics::accelerate(self.velocity[axis], thrust);
+        self.velocity[axis] = speed;
+        let crossed = physics::cells_crossed(self.offset[axis], speed);
+        self.offset[axis] += speed;
+        let (input, shift) = if crossed < 0 {
+            (back, CELL)
+        } else {
+            (forward, -CELL)
+        };
+        for _ in 0..crossed.unsigned_abs() {
+            if self.game_over {
+                return;
+            }
+            if !self.step_player(input) {
+                // Stopped dead by the edge of the board or zone
+                self.velocity[axis] = 0;
+                break;
+            }
+            self.offset[axis] += shift;
+        }
+        if thrust == 0 {
+            self.velocity[axis] = physics::coast(self.velocity[axis]);
+        }
+        // Settle in the middle of the cell once stopped
+        if self.velocity[axis] == 0 {
+            self.offset[axis] = CELL / 2;
+        }
+    }
+
+    // The player's speed along x and y in cells per tick
+    pub fn player_velocity(&self) -> (Fixed, Fixed) {
+        (self.velocity[0], self.velocity[1])
+    }
+
+    // The cell to draw the player in part way (0.0 to 1.0) through the next
+    // tick, following their momentum
+    pub fn player_at(&self, progress: f64) -> (u16, u16) {
+        let ahead = |axis: usize| {
+            let speed = physics::accelerate(self.velocity[axis], self.thrust[axis]);
+            physics::cells_crossed(self.offset[axis], (f64::from(speed) * progress) as Fixed)
+        };
+        let (top, bottom) = self.player_rows();
+        let x = (i32::from(self.player_x) + ahead(0)).clamp(0, i32::from(self.width.max(1) - 1));
+        let y = (i32::from(self.player_y) + ahead(1)).clamp(i32::from(top), i32::from(bottom));
+        (x as u16, y as u16)
+    }
+
+    // Whether anything moves far enough within a tick to be worth drawing
+    // between updates
+    pub fn in_motion(&self) -> bool {
+        self.velocity != [0; 2]
+            || self.thrust != [0; 2]
+            || self.blocks.iter().any(|block| block.speed >= CELL)
+    }
+
+    // Resize the playfield, keeping the player on the board and dropping
+    // blocks that no longer fit
+    pub fn resize(&mut self, width: u16, height: u16) {
+        if self.game_over {
+            return;
+        }
+
+        // Keep the player the same distance from the bottom edge
+        let from_bottom = self.height.saturating_sub(self.player_y);
+        self.width = width;
+        self.height = height;
+        self.player_x = self.player_x.min(width.saturating_sub(1));
+        self.player_y = self.clamp_row(height.saturating_sub(from_bottom));
+        self.blocks.retain(|b| b.x < width && b.y < height);
+        for block in &mut self.blocks {
+            block.width = block.width.min(width - block.x);
+        }
+        self.items.retain(|item| item.x < width && item.y < height);
+        self.hazards.retain(|hazard| match hazard.kind {
+            HazardKind::Column(x) => x < width,
+            HazardKind::Row(y) => y < height,
+        });
+        self.wave = None; // laid out for the old width
+        self.occupied.resize(width, height);
+        self.rebuild_occupancy();
+        self.rebuild_forecast();
+
+        if self.check_collision() {
+            self.hit();
+        }
+    }
+
+    // Update game state on each tick
+    pub fn update(&mut self) {
+        if self.game_over {
+            return;
+        }
+
+        self.invulnerable = self.invulnerable.saturating_sub(1);
+        self.dash_cooldown = self.dash_cooldown.saturating_sub(1);
+        for ticks in &mut self.effects {
+            *ticks = ticks.saturating_sub(1);
+        }
+
+        // The player moves first, so falling blocks are swept against where
+        // they end up
+        if self.rules.physics == Physics::Inertia {
+            self.glide(0);
+            self.glide(1);
+        }
+
+        // Spawn along the top row of the playable area: the next row of a
+        // running wave, or random rain with the odd wave scheduled in
+        let row_start = self.blocks.len();
+        if let Some(mut wave) = self.wave.take() {
+            if let Some((left, right)) = wave.rows[wave.next] {
+                for x in (0..left).chain(right..self.width) {
+                    self.blocks.push(FallingBlock::new(x, 0));
+                }
+            }
+            wave.next += 1;
+            if wave.next < wave.rows.len() {
+                self.wave = Some(wave);
+            }
+            if self.forecast.is_some() {
+                self.make_fair(row_start, 0, false);
+            }
+        } else {
+            let shrink = self.effect(PowerUp::Shrink) > 0;
+            self.spawn_rain(shrink);
+            if self.forecast.is_some() {
+                self.make_fair(row_start, fairness::MAX_REROLLS, shrink);
+            }
+
+            let wave_probability = self.rules.wave_probability;
+            if wave_probability > 0.0 && self.width > 0 && self.rng.gen_bool(wave_probability) {
+                self.start_wave();
+            }
+        }
+
+        // Power-ups are rolled only when enabled so runs without them draw
+        // the same random numbers as before they existed
+        let powerup_probability = self.rules.powerup_probability;
+        if powerup_probability > 0.0 && self.width > 0 && self.rng.gen_bool(powerup_probability) {
+            let x = self.rng.gen_range(0..self.width);
+            let kind = PowerUp::ALL[self.rng.gen_range(0..PowerUp::ALL.len())];
+            if !self.blocks.iter().any(|b| b.covers(x) && b.y == 0) {
+                self.items.push(Item { x, y: 0, kind });
+            }
+        }
+
+        // Advance telegraphed hazards, then maybe announce a new one. Row
+        // lasers only target rows the player can leave.
+        self.hazards.retain_mut(Hazard::advance);
+        // Waves are left alone so their path stays open.
+        let hazard_probability = self.rules.hazard_probability;
+        if hazard_probability > 0.0
+            && self.width > 0
+            && self.wave.is_none()
+            && self.rng.gen_bool(hazard_probability)
+        {
+            let (top, bottom) = self.player_rows();
+            let kind = if top < bottom && self.rng.gen_bool(0.5) {
+                HazardKind::Row(self.rng.gen_range(top..=bottom))
+            } else {
+                HazardKind::Column(self.rng.gen_range(0..self.width))
+            };
+            if !self.hazards.iter().any(|h| h.kind == kind) {
+                self.add_hazard(Hazard::new(kind));
+            }
+        }
+
+        // Move blocks down, sweeping each one across every cell it passed
+        // through so a block can never skip over the player
+        let mut hit = false;
+        for block in &mut self.blocks {
+            if block
+                .step(self.width)
+                .covers((self.player_x, self.player_y))
+            {
+                hit = true;
+            }
+        }
+        if hit || self.in_strike() {
+            self.hit();
+        }
+        if let Some(forecast) = &mut self.forecast {
+            forecast.advance();
+        }
+        // Remove blocks that fell off-screen
+        self.blocks.retain(|block| block.y < self.height);
+        self.rebuild_occupancy();
+
+        // Items fall the same way, drifting towards the player under a magnet,
+        // and are collected by the same sweep
+        let magnet = self.effect(PowerUp::Magnet) > 0;
+        let mut collected = Vec::new();
+        for item in &mut self.items {
+            let from = item.y;
+            item.y += 1;
+            if magnet {
+                item.x = match item.x.cmp(&self.player_x) {
+                    Ordering::Less => item.x + 1,
+                    Ordering::Greater => item.x - 1,
+                    Ordering::Equal => item.x,
+                };
+            }
+            if item.x == self.player_x && (from..=item.y).contains(&self.player_y) {
+                collected.push(item.kind);
+                item.y = self.height; // removed below
+            }
+        }
+        self.items.retain(|item| item.y < self.height);
+        if !self.game_over {
+            for kind in collected {
+                self.apply_powerup(kind);
+            }
+        }
+
+        // Increase score as you survive
+        self.score += if self.effect(PowerUp::Multiplier) > 0 {
+            2
+        } else {
+            1
+        };
+        self.tick += 1;
+    }
+
+    // Roll each column of the top row for a new block
+    fn spawn_rain(&mut self, shrink: bool) {
+        let mut spawn_probability = self.rules.difficulty.spawn_probability(self.score);
+        if shrink {
+            spawn_probability /= 2.0;
+        }
+        for x in 0..self.width {
+            if self.rng.gen_bool(spawn_probability) {
+                let block = if self.rules.obstacles {
+                    self.spawn_obstacle(x, shrink)
+                } else {
+                    FallingBlock::new(x, 0)
+                };
+                self.blocks.push(block);
+            }
+        }
+    }
+
+    // Make sure the row of blocks spawned from `row_start` on leaves the
+    // player a way out. Random rows are rerolled a few times first, then the
+    // blocks closest to the player are dropped until a path opens. Rows that
+    // only add to a fate already sealed are left alone.
+    fn make_fair(&mut self, row_start: usize, rerolls: u32, shrink: bool) {
+        let Some(mut base) = self.forecast.take() else {
+            return;
+        };
+        let player = (self.player_x, self.player_y);
+        let (width, rows) = (self.width, self.player_rows());
+        let overlay_of = |blocks: &[FallingBlock]| {
+            let mut overlay = Forecast::new(width, rows);
+            for block in blocks {
+                overlay.add(block);
+            }
+            overlay
+        };
+
+        let mut overlay = overlay_of(&self.blocks[row_start..]);
+        if !fairness::survivable(player, &[&base, &overlay])
+            && fairness::survivable(player, &[&base])
+        {
+            for _ in 0..rerolls {
+                self.blocks.truncate(row_start);
+                self.spawn_rain(shrink);
+                overlay = overlay_of(&self.blocks[row_start..]);
+                if fairness::survivable(player, &[&base, &overlay]) {
+                    break;
+                }
+            }
+            while !fairness::survivable(player, &[&base, &overlay]) {
+                let Some(nearest) = (row_start..self.blocks.len())
+                    .min_by_key(|&i| self.blocks[i].x.abs_diff(self.player_x))
+                else {
+                    break;
+                };
+                self.blocks.remove(nearest);
+                overlay = overlay_of(&self.blocks[row_start..]);
+            }
+        }
+        base.merge(&overlay);
+        self.forecast = Some(base);
+    }
+
+    // Start following every block and hazard again, as after the board
+    // changes, in fair mode
+    fn rebuild_forecast(&mut self) {
+        self.forecast = self.rules.fair.then(|| {
+            let mut forecast = Forecast::new(self.width, self.player_rows());
+            for block in &self.blocks {
+                forecast.add(block);
+            }
+            for hazard in &self.hazards {
+                forecast.add_hazard(hazard, 0);
+            }
+            forecast
+        });
+    }
+
+    // Announce a hazard during an update. In fair mode one the player
+    // couldn't get away from is dropped instead.
+    fn add_hazard(&mut self, hazard: Hazard) {
+        if let Some(forecast) = &mut self.forecast {
+            // It first advances on the next update, one slot on
+            let mut overlay = Forecast::new(self.width, self.rules.movement.rows(self.height));
+            overlay.add_hazard(&hazard, 1);
+            let player = (self.player_x, self.player_y);
+            if !fairness::survivable(player, &[forecast, &overlay]) {
+                return;
+            }
+            forecast.merge(&overlay);
+        }
+        self.hazards.push(hazard);
+    }
+
+    // A new obstacle on the top row, its kind weighted by difficulty. Slabs
+    // are cut down to a single cell while the shrink power-up is active.
+    fn spawn_obstacle(&mut self, x: u16, shrink: bool) -> FallingBlock {
+        let intensity = self.rules.difficulty.intensity(self.score);
+        let kind = BlockKind::choose(&mut self.rng, intensity);
+        let mut block = FallingBlock::with_kind(x, 0, kind);
+        match kind {
+            BlockKind::Slab if !shrink => {
+                block.width = self.rng.gen_range(3..=MAX_SLAB_WIDTH).min(self.width - x);
+            }
+            BlockKind::Bouncer | BlockKind::ZigZag if self.rng.gen_bool(0.5) => block.dx = -1,
+            _ => {}
+        }
+        block
+    }
+
+    // Pick a wave for the current level, weighted, and lay it out so the
+    // player can reach each gap from where they stand now
+    fn start_wave(&mut self) {
+        let patterns = Arc::clone(&self.patterns);
+        let level = self.level();
+        let eligible: Vec<&Pattern> = patterns.iter().filter(|p| p.min_level <= level).collect();
+        let total = eligible
+            .iter()
+            .fold(0u32, |total, p| total.saturating_add(p.weight));
+        if total == 0 {
+            return;
+        }
+        let mut roll = self.rng.gen_range(0..total);
+        let Some(pattern) = eligible.into_iter().find(|p| {
+            let found = roll < p.weight;
+            roll = roll.saturating_sub(p.weight);
+            found
+        }) else {
+            return;
+        };
+        let mirrored = pattern.mirror && self.rng.gen_bool(0.5);
+        let rows = pattern.resolve(self.width, mirrored, self.player_x, self.player_y);
+        self.wave = Some(Wave { rows, next: 0 });
+    }
+
+    fn rebuild_occupancy(&mut self) {
+        self.occupied.clear();
+        for block in &self.blocks {
+            for x in block.x..block.x.saturating_add(block.width) {
+                self.occupied.set(x, block.y);
+            }
+        }
+    }
+
+    // Whether a striking hazard covers the player's cell
+    fn in_strike(&self) -> bool {
+        self.hazards
+            .iter()
+            .any(|h| h.is_striking() && h.covers(self.player_x, self.player_y))
+    }
+
+    // Check whether a block currently occupies the player's cell
+    pub fn check_collision(&self) -> bool {
+        self.is_block_at(self.player_x, self.player_y)
+    }
+
+    // Whether a block currently occupies the given cell
+    pub fn is_block_at(&self, x: u16, y: u16) -> bool {
+        self.occupied.get(x, y)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::difficulty::{Curve, Difficulty};
+
+    // A board where nothing spawns, so tests place every block themselves
+    fn empty_game() -> Game {
+        let rules = Rules {
+            difficulty: Difficulty {
+                base_spawn_probability: 0.0,
+                max_spawn_probability: 0.0,
+                ..Difficulty::with_curve(Curve::Flat)
+            },
+            ..Rules::legacy()
+        };
+        Game::new(20, 10, 1).with_rules(rules)
+    }
+
+    // Fair spawning with a block in every column of every row, before any
+    // are taken out to leave a way through
+    fn full_rows_game(width: u16, hazard_probability: f64) -> Game {
+        let rules = Rules {
+            difficulty: Difficulty {
+                base_spawn_probability: 1.0,
+                max_spawn_probability: 1.0,
+                ..Difficulty::with_curve(Curve::Flat)
+            },
+            hazard_probability,
+            fair: true,
+            ..Rules::legacy()
+        };
+        Game::new(width, 10, 1).with_rules(rules)
+    }
+
+    #[test]
+    fn fair_spawning_carves_a_gap_in_a_full_row() {
+        let mut game = full_rows_game(20, 0.0);
+        game.update();
+        assert!(game.blocks().len() < 20);
+        assert!(!game.is_block_at(game.player_x, 1));
+        for _ in 0..100 {
+            game.update();
+        }
+        assert!(!game.game_over);
+    }
+
+    #[test]
+    fn fair_spawning_drops_unavoidable_hazards() {
+        // With the columns either side always full, a strike on the player's
+        // column could never be dodged
+        let mut game = full_rows_game(3, 1.0);
+        for _ in 0..200 {
+            game.update();
+        }
+        assert!(!game.game_over);
+    }
+
+    #[test]
+    fn moving_into_a_block_between_ticks_is_a_hit() {
+        let mut game = empty_game();
+        let (x, y) = (game.player_x, game.player_y);
+        game.add_block(FallingBlock::new(x - 1, y));
+        game.handle_input(Input::Left);
+        assert_eq!((game.player_x, game.player_y), (x - 1, y));
+        assert!(game.game_over);
+    }
+
+    #[test]
+    fn fast_block_cannot_skip_over_the_player() {
+        let mut game = empty_game();
+        let (x, y) = (game.player_x, game.player_y);
+        game.add_block(FallingBlock::with_kind(x, y - 1, BlockKind::Fast));
+        game.update();
+        assert_eq!(game.blocks()[0].y, y + 1);
+        assert!(game.game_over);
+    }
+
+    // Everything a run's outcome depends on, for comparing two runs
+    fn snapshot(game: &Game) -> impl PartialEq + std::fmt::Debug {
+        let blocks: Vec<_> = game
+            .blocks()
+            .iter()
+            .map(|b| (b.x, b.y, b.kind, b.width, b.dx))
+            .collect();
+        let items: Vec<_> = game.items().iter().map(|i| (i.x, i.y, i.kind)).collect();
+        (
+            (game.tick, game.score, game.lives, game.game_over),
+            (game.player_x, game.player_y),
+            blocks,
+            items,
+        )
+    }
+
+    // Run a long game with every feature on, feeding the same inputs
+    fn play(seed: u64) -> Game {
+        let rules = Rules {
+            lives: 1000,
+            ..Rules::default()
+        };
+        let mut game = Game::new(30, 16, seed).with_rules(rules);
+        let inputs = [
+            Input::Left,
+            Input::Up,
+            Input::Right,
+            Input::Dash,
+            Input::Down,
+        ];
+        for tick in 0..500 {
+            if tick % 3 == 0 {
+                game.handle_input(inputs[tick / 3 % inputs.len()]);
+            }
+            game.update();
+        }
+        game
+    }
+
+    #[test]
+    fn same_seed_and_inputs_give_the_same_run() {
+        assert_eq!(snapshot(&play(7)), snapshot(&play(7)));
+        assert_ne!(snapshot(&play(7)), snapshot(&play(8)));
+    }
+}
@@ -0,0 +1,53 @@
+/// A bitset with one bit per playfield cell, stored row by row.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Grid {
+    width: u16,
+    height: u16,
+    bits: Vec<u64>,
+}
+
+impl Grid {
+    pub fn new(width: u16, height: u16) -> Self {
+        let cells = usize::from(width) * usize::from(height);
+        Self {
+            width,
+            height,
+            bits: vec![0; cells.div_ceil(64)],
+        }
+    }
+
+    pub fn width(&self) -> u16 {
+        self.width
+    }
+
+    pub fn height(&self) -> u16 {
+        self.height
+    }
+
+    pub fn clear(&mut self) {
+        self.bits.fill(0);
+    }
+
+    // Change the dimensions, clearing every cell
+    pub fn resize(&mut self, width: u16, height: u16) {
+        *self = Self::new(width, height);
+    }
+
+    fn index(&self, x: u16, y: u16) -> Option<usize> {
+        (x < self.width && y < self.height)
+            .then(|| usize::from(y) * usize::from(self.width) + usize::from(x))
+    }
+
+    // Cells outside the grid are ignored
+    pub fn set(&mut self, x: u16, y: u16) {
+        if let Some(i) = self.index(x, y) {
+            self.bits[i / 64] |= 1 << (i % 64);
+        }
+    }
+
+    // Cells outside the grid are never occupied
+    pub fn get(&self, x: u16, y: u16) -> bool {
+        self.index(x, y)
+            .is_some_and(|i| self.bits[i / 64] & (1 << (i % 64)) != 0)
+    }
+}
@@ -0,0 +1,69 @@
+// Ticks a hazard is telegraphed before it strikes
+pub const WARNING_TICKS: u32 = 8;
+
+// Ticks a strike stays lethal
+pub const STRIKE_TICKS: u32 = 2;
+
+/// What a hazard hits when it goes off.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HazardKind {
+    // Strikes every cell in a column
+    Column(u16),
+    // A laser across every cell in a row
+    Row(u16),
+}
+
+/// Where a hazard is in its life.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HazardPhase {
+    Warning { ticks_left: u32 },
+    Strike { ticks_left: u32 },
+}
+
+/// A strike announced ahead of time so the player can react.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Hazard {
+    pub kind: HazardKind,
+    pub phase: HazardPhase,
+}
+
+impl Hazard {
+    pub fn new(kind: HazardKind) -> Self {
+        Self {
+            kind,
+            phase: HazardPhase::Warning {
+                ticks_left: WARNING_TICKS,
+            },
+        }
+    }
+
+    pub fn is_striking(&self) -> bool {
+        matches!(self.phase, HazardPhase::Strike { .. })
+    }
+
+    // Whether the hazard's column or row contains the given cell
+    pub fn covers(&self, x: u16, y: u16) -> bool {
+        match self.kind {
+            HazardKind::Column(column) => column == x,
+            HazardKind::Row(row) => row == y,
+        }
+    }
+
+    // Advance one tick: a warning counts down and turns into a strike, a
+    // strike counts down and ends. Returns false once the hazard is over.
+    pub(crate) fn advance(&mut self) -> bool {
+        self.phase = match self.phase {
+            HazardPhase::Warning { ticks_left } if ticks_left > 1 => HazardPhase::Warning {
+                ticks_left: ticks_left - 1,
+            },
+            HazardPhase::Warning { .. } => HazardPhase::Strike {
+                ticks_left: STRIKE_TICKS,
+            },
+            HazardPhase::Strike { ticks_left } if ticks_left > 1 => HazardPhase::Strike {
+                ticks_left: ticks_left - 1,
+            },
+            HazardPhase::Strike { .. } => return false,
+        };
+        true
+    }
+}
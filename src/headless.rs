@@ -0,0 +1,161 @@
+use std::error::Error;
+use std::time::{Duration, Instant};
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use ratatui::{backend::TestBackend, Terminal};
+
+use dodge::{Game, Input, Replay};
+
+use crate::cli::{BenchOptions, Bot, GameOptions, SimulateOptions};
+use crate::config::Config;
+use crate::ui;
+
+// Bench board size in terminal cells when none is given: a 300x100 terminal
+// minus the border
+const BENCH_SIZE: (u16, u16) = (298, 98);
+
+fn new_game(options: &GameOptions, config: &Config) -> Game {
+    let (width, height) = options.size();
+    config.gameplay.new_game(width, height, options.seed())
+}
+
+// Time raw simulation updates, then full frames rendered to an off-screen
+// terminal, restarting the game whenever it ends
+pub fn bench(options: &BenchOptions, config: &Config) -> Result<(), Box<dyn Error>> {
+    let resolution = config.visuals.resolution;
+    let (width, height) = options
+        .game
+        .size
+        .unwrap_or_else(|| resolution.board_size(BENCH_SIZE));
+    let seed = options.game.seed();
+    let advance = |game: &mut Game| {
+        game.update();
+        if game.game_over {
+            *game = config
+                .gameplay
+                .new_game(width, height, game.seed.wrapping_add(1));
+        }
+    };
+
+    let mut game = config.gameplay.new_game(width, height, seed);
+    let started = Instant::now();
+    for _ in 0..options.ticks {
+        advance(&mut game);
+    }
+    let elapsed = started.elapsed();
+    println!(
+        "simulated {} ticks on {width}x{height} in {:.3} ms ({:.0} ticks/s)",
+        options.ticks,
+        elapsed.as_secs_f64() * 1e3,
+        options.ticks as f64 / elapsed.as_secs_f64().max(f64::EPSILON),
+    );
+
+    // Fill the board before timing so frames are representative of real play
+    let mut game = config.gameplay.new_game(width, height, seed);
+    for _ in 0..height {
+        advance(&mut game);
+    }
+    let (columns, rows) = resolution.screen_size((width, height));
+    let mut terminal = Terminal::new(TestBackend::new(columns + 2, rows + 2))?;
+    let mut total = Duration::ZERO;
+    let mut slowest = Duration::ZERO;
+    for _ in 0..options.frames {
+        advance(&mut game);
+        let title = ui::status_title(&game);
+        let started = Instant::now();
+        terminal.draw(|f| ui::draw(f, &game, &config.visuals, &title, 0.0))?;
+        let elapsed = started.elapsed();
+        total += elapsed;
+        slowest = slowest.max(elapsed);
+    }
+    let frames = options.frames.max(1) as u32;
+    println!(
+        "rendered {} frames at {}x{} ({resolution}) in {:.3} ms ({:.3} ms/frame mean, {:.3} ms max)",
+        options.frames,
+        columns + 2,
+        rows + 2,
+        total.as_secs_f64() * 1e3,
+        (total / frames).as_secs_f64() * 1e3,
+        slowest.as_secs_f64() * 1e3,
+    );
+    Ok(())
+}
+
+// Play a game without a terminal and print the outcome as key=value lines
+pub fn simulate(options: &SimulateOptions, config: &Config) -> Result<(), Box<dyn Error>> {
+    let game = match &options.replay {
+        Some(path) => {
+            let replay = Replay::load(path).map_err(|err| format!("{}: {err}", path.display()))?;
+            let mut player = replay.player();
+            while !player.is_finished() && player.game().tick < options.ticks {
+                player.step();
+            }
+            player.game().clone()
+        }
+        None => {
+            let mut game = new_game(&options.game, config);
+            let mut replay = Replay::new(&game);
+            let mut rng = ChaCha8Rng::seed_from_u64(game.seed);
+            while !game.game_over && game.tick < options.ticks {
+                if let Some(input) = choose_input(options.bot, &game, &mut rng) {
+                    replay.record(game.tick, input);
+                    game.handle_input(input);
+                }
+                game.update();
+            }
+            if let Some(path) = &options.record {
+                replay.finish(game.tick);
+                replay.save(path)?;
+            }
+            game
+        }
+    };
+
+    println!("seed={}", game.seed);
+    println!("size={}x{}", game.width, game.height);
+    println!("ticks={}", game.tick);
+    println!("score={}", game.score);
+    println!("level={}", game.level());
+    println!("lives={}", game.lives);
+    println!("game_over={}", game.game_over);
+    Ok(())
+}
+
+fn choose_input(bot: Bot, game: &Game, rng: &mut ChaCha8Rng) -> Option<Input> {
+    match bot {
+        Bot::Idle => None,
+        Bot::Random => match rng.gen_range(0..3) {
+            0 => Some(Input::Left),
+            1 => Some(Input::Right),
+            _ => None,
+        },
+        Bot::Dodge => dodge(game),
+    }
+}
+
+// Step towards whichever neighbouring column has the most room above the player
+fn dodge(game: &Game) -> Option<Input> {
+    let clearance = |x: u16| {
+        (0..=game.player_y)
+            .rev()
+            .take_while(|&y| !game.is_block_at(x, y))
+            .count()
+    };
+
+    let mut best = (clearance(game.player_x), None);
+    if game.player_x > 0 {
+        let left = clearance(game.player_x - 1);
+        if left > best.0 {
+            best = (left, Some(Input::Left));
+        }
+    }
+    if game.player_x + 1 < game.width {
+        let right = clearance(game.player_x + 1);
+        if right > best.0 {
+            best = (right, Some(Input::Right));
+        }
+    }
+    best.1
+}
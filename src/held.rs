@@ -0,0 +1,77 @@
+use std::time::{Duration, Instant};
+
+use crossterm::event::KeyEventKind;
+use dodge::Input;
+
+// How long a direction has to be held before it moves the player every tick,
+// so a quick tap that straddles a tick still moves one cell
+pub const HOLD_DELAY: Duration = Duration::from_millis(150);
+
+// Tracks which movement keys are held down. Terminals speaking the kitty
+// keyboard protocol report repeats and releases, and while one is held the
+// player moves once per tick rather than at the keyboard's autorepeat rate.
+// Other terminals only send presses, each of which moves the player once.
+pub struct HeldKeys {
+    // Directions held down with when they were pressed, most recent last
+    held: Vec<(Input, Instant)>,
+    delay: Duration,
+    // Set once the terminal sends a repeat or release, proving it reports them
+    reports_releases: bool,
+}
+
+impl HeldKeys {
+    pub fn new(delay: Duration) -> Self {
+        Self {
+            held: Vec::new(),
+            delay,
+            reports_releases: false,
+        }
+    }
+
+    // Note a key event for a direction, returning whether the player should
+    // move right away. Repeats are left to the per-tick movement.
+    pub fn key(&mut self, input: Input, kind: KeyEventKind) -> bool {
+        match kind {
+            KeyEventKind::Press => {
+                self.held.retain(|&(held, _)| held != input);
+                self.held.push((input, Instant::now()));
+                true
+            }
+            KeyEventKind::Repeat => {
+                self.reports_releases = true;
+                false
+            }
+            KeyEventKind::Release => {
+                self.reports_releases = true;
+                self.held.retain(|&(held, _)| held != input);
+                false
+            }
+        }
+    }
+
+    // Forget every key, for when releases may be missed (pausing)
+    pub fn clear(&mut self) {
+        self.held.clear();
+    }
+
+    // Moves to make this tick: the latest direction held on each axis, once
+    // it has been down long enough
+    pub fn moves(&self) -> Vec<Input> {
+        if !self.reports_releases {
+            return Vec::new();
+        }
+        let horizontal = |input: &Input| matches!(input, Input::Left | Input::Right);
+        let latest = |on_axis: &dyn Fn(&Input) -> bool| {
+            self.held
+                .iter()
+                .rev()
+                .find(|(input, _)| on_axis(input))
+                .filter(|(_, since)| since.elapsed() >= self.delay)
+                .map(|&(input, _)| input)
+        };
+        latest(&horizontal)
+            .into_iter()
+            .chain(latest(&|input| !horizontal(input)))
+            .collect()
+    }
+}
@@ -0,0 +1,34 @@
+//! Headless simulation for the dodge game.
+//!
+//! The terminal front-end lives in the `dodge` binary; everything needed to
+//! drive a game from tests, bots or tools is exposed here.
+
+pub mod difficulty;
+pub mod fairness;
+pub mod game;
+pub mod grid;
+pub mod hazard;
+pub mod obstacle;
+pub mod pattern;
+pub mod physics;
+pub mod powerup;
+pub mod replay;
+pub mod resolution;
+pub mod rules;
+pub mod scores;
+
+pub use difficulty::{Curve, Difficulty, NEW_BLOCK_PROBABILITY, TICK_RATE};
+pub use fairness::Forecast;
+pub use game::{Game, Input, DASH_CELLS};
+pub use grid::Grid;
+pub use hazard::{Hazard, HazardKind, HazardPhase};
+pub use obstacle::{BlockKind, FallingBlock};
+pub use pattern::{Pattern, PatternError, PatternRow};
+pub use physics::{Fixed, CELL};
+pub use powerup::{Item, PowerUp};
+pub use replay::{EventKind, Replay, ReplayError, ReplayEvent, ReplayPlayer};
+pub use resolution::Resolution;
+pub use rules::{
+    Movement, Physics, Rules, DEFAULT_ZONE_ROWS, HAZARD_PROBABILITY, INVULNERABLE_TICKS,
+    POWERUP_PROBABILITY, WAVE_PROBABILITY,
+};
@@ -1,169 +1,775 @@
 use std::error::Error;
-use std::io;
-use std::time::{Duration, Instant};
-
-use crossterm::{
-    event::{self, Event, KeyCode},
-    execute,
-    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
-};
-use ratatui::{
-    backend::CrosstermBackend,
-    layout::Alignment,
-    style::{Color, Style},
-    text::{Span, Spans},
-    widgets::{Block as WidgetBlock, Borders, Paragraph},
-    Terminal,
-};
-use rand::Rng;
-
-// Configuration constants
-const TICK_RATE: Duration = Duration::from_millis(200);
-const NEW_BLOCK_PROBABILITY: f64 = 0.1; // probability per column per tick
-
-#[derive(Clone)]
-struct FallingBlock {
-    x: u16,
-    y: u16,
+use std::process::ExitCode;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use ratatui::text::Spans;
+
+use dodge::scores::{self, HighScores, ScoreEntry};
+use dodge::{Game, Input, Physics, Replay};
+
+mod cli;
+mod config;
+mod controls;
+mod headless;
+mod held;
+mod pointer;
+mod terminal;
+mod timing;
+mod ui;
+
+use cli::{Command, GameOptions, PlayOptions, ReplayOptions, ScoresOptions, REPLAY_SPEEDS};
+use config::{Config, Visuals};
+use controls::{Action, Key, Keymap, Mouse};
+use held::{HeldKeys, HOLD_DELAY};
+use pointer::Pointer;
+use terminal::TerminalGuard;
+use timing::{FixedTimestep, MAX_CATCH_UP};
+use ui::{board_cell, draw, draw_popup, status_title};
+
+// Longest wait for input while nothing is scheduled, so signals are noticed
+const IDLE_POLL: Duration = Duration::from_millis(250);
+
+// Time between frames drawn in the middle of a tick while something moves
+// fast enough to show it
+const FRAME_INTERVAL: Duration = Duration::from_millis(33);
+
+// How far into the next tick to draw the game, or 0.0 with interpolation off
+fn tick_progress(visuals: &Visuals, clock: &FixedTimestep, step: Duration) -> f64 {
+    if visuals.interpolate {
+        clock.progress(step)
+    } else {
+        0.0
+    }
+}
+
+// Whether frames are worth drawing before the next tick
+fn is_animating(visuals: &Visuals, clock: &FixedTimestep, game: &Game) -> bool {
+    visuals.interpolate && !clock.is_paused() && game.in_motion()
 }
 
-struct Game {
-    player_x: u16,
-    player_y: u16,
-    blocks: Vec<FallingBlock>,
-    score: u64,
-    width: u16,  // playable width (inner area)
-    height: u16, // playable height (inner area)
+// What to do once a round has ended
+enum Restart {
+    SameSeed,
+    NewSeed,
+    Quit,
 }
 
-impl Game {
-    fn new(width: u16, height: u16) -> Self {
-        Self {
-            player_x: width / 2,
-            player_y: height.saturating_sub(2),
-            blocks: Vec::new(),
-            score: 0,
-            width,
-            height,
+// Play rounds until the player quits, returning the last game
+fn play(
+    terminal: &mut TerminalGuard,
+    options: &PlayOptions,
+    config: &Config,
+) -> Result<Game, Box<dyn Error>> {
+    let mut seed = options.game.seed();
+    let mut keymap = config.controls.keymap();
+    loop {
+        let game = play_round(terminal, options, config, &mut keymap, seed)?;
+        if !game.game_over || terminal.interrupted() {
+            return Ok(game);
+        }
+        match game_over_screen(terminal, config, &keymap, &game)? {
+            Restart::SameSeed => {}
+            Restart::NewSeed => seed = rand::random(),
+            Restart::Quit => return Ok(game),
         }
     }
+}
+
+fn play_round(
+    terminal: &mut TerminalGuard,
+    options: &PlayOptions,
+    config: &Config,
+    keymap: &mut Keymap,
+    seed: u64,
+) -> Result<Game, Box<dyn Error>> {
+    // Get terminal size and compute playable area (subtract border: 1 on each side)
+    // in board cells, unless a fixed arena was requested
+    let resolution = config.visuals.resolution;
+    let outer_size = terminal.size()?;
+    let (playable_width, playable_height) = options.arena().unwrap_or_else(|| {
+        resolution.board_size((
+            outer_size.width.saturating_sub(2),
+            outer_size.height.saturating_sub(2),
+        ))
+    });
+
+    let game = config
+        .gameplay
+        .new_game(playable_width, playable_height, seed);
+    let rules = game.rules.scaled_to(resolution);
+    let mut game = game.with_rules(rules);
+    let mut replay = Replay::new(&game);
+    replay.resolution = resolution;
+    let started = Instant::now();
+    let mut clock = FixedTimestep::new();
+    let mut paused_at: Option<Instant> = None;
+    let mut paused_total = Duration::ZERO;
+    let mut dirty = true;
+    // With inertia a held key is thrust, which should start straight away
+    let mut held = HeldKeys::new(match game.rules.physics {
+        Physics::Step => HOLD_DELAY,
+        Physics::Inertia => Duration::ZERO,
+    });
+    let mut pointer = Pointer::new(config.controls.mouse);
+    terminal.report_key_releases(true);
+    terminal.capture_mouse(config.controls.mouse != Mouse::Off)?;
+
+    'game_loop: loop {
+        if terminal.interrupted() {
+            break 'game_loop;
+        }
 
-    // Update game state on each tick
-    fn update(&mut self) {
-        let mut rng = rand::thread_rng();
+        // Only redraw when something changed
+        if dirty {
+            let title = status_title(&game);
+            let progress = tick_progress(&config.visuals, &clock, game.tick_rate());
+            terminal.draw(|f| {
+                draw(f, &game, &config.visuals, &title, progress);
+                if paused_at.is_some() {
+                    draw_popup(
+                        f,
+                        "Paused",
+                        vec![
+                            Spans::from(format!("{} to resume", keymap.describe(Action::Pause))),
+                            Spans::from(format!("{} to quit", keymap.describe(Action::Quit))),
+                            Spans::from(format!(
+                                "{} to change controls",
+                                keymap.describe(Action::Controls)
+                            )),
+                        ],
+                    );
+                }
+            })?;
+            dirty = false;
+        }
 
-        // Spawn new blocks along the top row of the playable area
-        for x in 0..self.width {
-            if rng.gen_bool(NEW_BLOCK_PROBABILITY) {
-                self.blocks.push(FallingBlock { x, y: 0 });
+        // Sleep until the next tick is due or an event arrives, waking for
+        // in-between frames while something is moving
+        let animating = is_animating(&config.visuals, &clock, &game);
+        let mut timeout = clock
+            .until_next(game.tick_rate())
+            .map_or(IDLE_POLL, |due| due.min(IDLE_POLL));
+        if animating {
+            timeout = timeout.min(FRAME_INTERVAL);
+        }
+        if !event::poll(timeout)? {
+            dirty |= animating;
+        } else {
+            dirty = true;
+            match event::read()? {
+                // Releases only matter for letting go of a direction
+                Event::Key(key) if key.kind == KeyEventKind::Release => {
+                    if let Some(input) = keymap.action(&key).and_then(Action::direction) {
+                        held.key(input, key.kind);
+                    }
+                }
+                Event::Key(key) => match keymap.action(&key) {
+                    Some(Action::Quit) => break 'game_loop,
+                    // Pausing stops the clock so no time passes for the game.
+                    // Holding the key down doesn't keep toggling it.
+                    Some(Action::Pause) if key.kind == KeyEventKind::Press => {
+                        match paused_at.take() {
+                            Some(at) => paused_total += at.elapsed(),
+                            None => paused_at = Some(Instant::now()),
+                        }
+                        clock.set_paused(paused_at.is_some());
+                        held.clear();
+                    }
+                    Some(Action::Dash) if key.kind == KeyEventKind::Press && !clock.is_paused() => {
+                        replay.record(game.tick, Input::Dash);
+                        game.handle_input(Input::Dash);
+                    }
+                    // The menu pauses the game if it isn't already
+                    Some(Action::Controls) if key.kind == KeyEventKind::Press => {
+                        if paused_at.is_none() {
+                            paused_at = Some(Instant::now());
+                            clock.set_paused(true);
+                            held.clear();
+                        }
+                        if controls_menu(terminal, config, keymap, &game)? {
+                            break 'game_loop;
+                        }
+                    }
+                    Some(action) if !clock.is_paused() => {
+                        if let Some(input) = action.direction() {
+                            pointer.release();
+                            if held.key(input, key.kind) {
+                                replay.record(game.tick, input);
+                                game.handle_input(input);
+                            }
+                        }
+                    }
+                    _ => {}
+                },
+                Event::Mouse(mouse) if !clock.is_paused() => {
+                    let cell = board_cell(
+                        terminal.size()?,
+                        &game,
+                        &config.visuals,
+                        (mouse.column, mouse.row),
+                    );
+                    pointer.event(mouse.kind, cell);
+                }
+                // A fixed arena keeps its size; otherwise the playfield follows the terminal
+                Event::Resize(width, height) if options.arena().is_none() => {
+                    let (width, height) =
+                        resolution.board_size((width.saturating_sub(2), height.saturating_sub(2)));
+                    replay.record_resize(game.tick, width, height);
+                    game.resize(width, height);
+                }
+                _ => {}
+            }
+            if game.game_over {
+                break 'game_loop;
             }
         }
 
-        // Move blocks down and remove those off-screen
-        for block in &mut self.blocks {
-            block.y += 1;
+        // Run as many fixed-length ticks as the elapsed time covers
+        clock.advance();
+        let mut steps = 0;
+        while clock.take_step(game.tick_rate()) {
+            // Held directions and the mouse move the player at the game's pace
+            let mut moves = held.moves();
+            moves.extend(pointer.moves(&game));
+            for input in moves {
+                replay.record(game.tick, input);
+                game.handle_input(input);
+            }
+            if game.game_over {
+                break 'game_loop;
+            }
+            game.update();
+            dirty = true;
+            if game.game_over {
+                break 'game_loop;
+            }
+            steps += 1;
+            if steps == MAX_CATCH_UP {
+                clock.discard_backlog();
+            }
         }
-        self.blocks.retain(|block| block.y < self.height);
+    }
+
+    terminal.report_key_releases(false);
+    terminal.capture_mouse(false)?;
 
-        // Increase score as you survive
-        self.score += 1;
+    if let Some(path) = &options.record {
+        replay.finish(game.tick);
+        replay.save(path)?;
     }
 
-    // Check for collision between the player and any block
-    fn check_collision(&self) -> bool {
-        self.blocks
-            .iter()
-            .any(|b| b.x == self.player_x && b.y == self.player_y)
+    if game.game_over && !terminal.interrupted() {
+        let entry = ScoreEntry {
+            name: String::new(),
+            score: game.score,
+            date: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs()),
+            seed: game.seed,
+            duration: started.elapsed().saturating_sub(paused_total),
+            mode: options.mode_name(&config.gameplay.rules()),
+            width: replay.width,
+            height: replay.height,
+        };
+        record_high_score(terminal, config, &game, entry)?;
     }
+    Ok(game)
 }
 
-fn main() -> Result<(), Box<dyn Error>> {
-    // Set up terminal
-    enable_raw_mode()?;
-    let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen)?;
-    let backend = CrosstermBackend::new(stdout);
-    let mut terminal = Terminal::new(backend)?;
+// Show the final score and ask whether to play again
+fn game_over_screen(
+    terminal: &mut TerminalGuard,
+    config: &Config,
+    keymap: &Keymap,
+    game: &Game,
+) -> Result<Restart, Box<dyn Error>> {
+    while !terminal.interrupted() {
+        let title = status_title(game);
+        terminal.draw(|f| {
+            draw(f, game, &config.visuals, &title, 0.0);
+            draw_popup(
+                f,
+                "Game Over",
+                vec![
+                    Spans::from(format!("Final score: {}", game.score)),
+                    Spans::from(format!("Seed: {}", game.seed)),
+                    Spans::from(""),
+                    Spans::from("r  play again (same seed)"),
+                    Spans::from("n  play again (new seed)"),
+                    Spans::from(format!("{}  quit", keymap.describe(Action::Quit))),
+                ],
+            );
+        })?;
 
-    // Get terminal size and compute playable area (subtract border: 1 on each side)
-    let outer_size = terminal.size()?;
-    let playable_width = outer_size.width.saturating_sub(2);
-    let playable_height = outer_size.height.saturating_sub(2);
+        if event::poll(Duration::from_millis(100))? {
+            // Skip releases of keys pressed during the round
+            if let Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Release {
+                    continue;
+                }
+                if keymap.action(&key) == Some(Action::Quit) {
+                    return Ok(Restart::Quit);
+                }
+                match key.code {
+                    KeyCode::Char('r') => return Ok(Restart::SameSeed),
+                    KeyCode::Char('n') => return Ok(Restart::NewSeed),
+                    _ => {}
+                }
+            }
+        }
+    }
+    Ok(Restart::Quit)
+}
 
-    let mut game = Game::new(playable_width, playable_height);
-    let mut last_tick = Instant::now();
+// List the bindings over the paused game and let the player change them
+// and save them to the config file. Returns true if Ctrl-C asked to quit.
+fn controls_menu(
+    terminal: &mut TerminalGuard,
+    config: &Config,
+    keymap: &mut Keymap,
+    game: &Game,
+) -> Result<bool, Box<dyn Error>> {
+    let mut selected = 0;
+    // Set while waiting for a key: whether it is added rather than replacing
+    let mut capture: Option<bool> = None;
+    let mut message = String::new();
 
-    'game_loop: loop {
-        // Draw the game frame
+    while !terminal.interrupted() {
+        let keys: Vec<String> = Action::ALL.map(|a| keymap.describe(a)).into();
+        let width = keys.iter().map(|k| k.chars().count()).max().unwrap_or(0);
+        let mut lines: Vec<Spans> = Action::ALL
+            .iter()
+            .zip(&keys)
+            .enumerate()
+            .map(|(i, (action, keys))| {
+                let marker = if i == selected { '>' } else { ' ' };
+                Spans::from(format!("{marker} {:<10}  {keys:<width$}", action.label()))
+            })
+            .collect();
+        lines.push(Spans::from(""));
+        match capture {
+            Some(_) => lines.push(Spans::from(format!(
+                "Press a key for {} (Esc cancels)",
+                Action::ALL[selected].label()
+            ))),
+            None => {
+                lines.push(Spans::from("Enter rebind  a add  Del clear"));
+                lines.push(Spans::from("s save  Esc back"));
+            }
+        }
+        if !message.is_empty() {
+            lines.push(Spans::from(message.as_str()));
+        }
+        let title = status_title(game);
         terminal.draw(|f| {
-            let outer_area = f.size();
-            let block = WidgetBlock::default()
-                .borders(Borders::ALL)
-                .title(format!("Score: {}", game.score));
-            let inner_area = block.inner(outer_area);
-
-            // Build a vector of Spans representing each row in the playable area
-            let mut lines = Vec::with_capacity(inner_area.height as usize);
-            for y in 0..inner_area.height {
-                let mut spans = Vec::with_capacity(inner_area.width as usize);
-                for x in 0..inner_area.width {
-                    if y == game.player_y && x == game.player_x {
-                        // Player drawn with a contrasting style
-                        spans.push(Span::styled(
-                            "@",
-                            Style::default().fg(Color::Black).bg(Color::Yellow),
-                        ));
-                    } else if game.blocks.iter().any(|b| b.x == x && b.y == y) {
-                        spans.push(Span::raw("#"));
-                    } else {
-                        spans.push(Span::raw(" "));
-                    }
+            draw(f, game, &config.visuals, &title, 0.0);
+            draw_popup(f, "Controls", lines);
+        })?;
+
+        if !event::poll(Duration::from_millis(100))? {
+            continue;
+        }
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        let action = Action::ALL[selected];
+        if key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL) {
+            return Ok(true);
+        }
+        if let Some(add) = capture {
+            // Shift and friends arrive on their own first on terminals
+            // reporting every key; wait for the key they modify
+            if matches!(key.code, KeyCode::Modifier(_)) {
+                continue;
+            }
+            if key.code == KeyCode::Esc {
+                capture = None;
+                continue;
+            }
+            // Only keys the config file can name again are bound, so a
+            // saved keymap always loads
+            let key = Key::from_event(&key);
+            if !key.has_name() {
+                message = "That key can't be bound".into();
+                continue;
+            }
+            if add {
+                keymap.bind(action, key);
+            } else {
+                keymap.set(action, &[key]);
+            }
+            capture = None;
+            message.clear();
+            continue;
+        }
+        match key.code {
+            KeyCode::Up | KeyCode::Char('k') => selected = selected.saturating_sub(1),
+            KeyCode::Down | KeyCode::Char('j') => {
+                selected = (selected + 1).min(Action::ALL.len() - 1)
+            }
+            KeyCode::Enter => capture = Some(false),
+            KeyCode::Char('a') => capture = Some(true),
+            KeyCode::Backspace | KeyCode::Delete => keymap.set(action, &[]),
+            KeyCode::Char('s') => {
+                message = match (config.save_controls(keymap), &config.path) {
+                    (Ok(()), Some(path)) => format!("Saved to {}", path.display()),
+                    (Ok(()), None) => "Saved".into(),
+                    (Err(err), _) => format!("Could not save: {err}"),
+                };
+            }
+            KeyCode::Esc => return Ok(false),
+            _ => {}
+        }
+    }
+    Ok(false)
+}
+
+// Offer a name prompt if the run made the high-score table, and save it
+fn record_high_score(
+    terminal: &mut TerminalGuard,
+    config: &Config,
+    game: &Game,
+    mut entry: ScoreEntry,
+) -> Result<(), Box<dyn Error>> {
+    let Some(path) = HighScores::default_path() else {
+        return Ok(());
+    };
+    // A broken score file is reported rather than ending the session, and
+    // left alone so nothing in it is lost
+    let mut high_scores = match HighScores::load(&path) {
+        Ok(high_scores) => high_scores,
+        Err(err) => {
+            let message = format!("Could not load {}: {err}", path.display());
+            return notice(terminal, config, game, "High scores", &message);
+        }
+    };
+    let Some(rank) = high_scores.rank(&entry.mode, entry.width, entry.height, entry.score) else {
+        return Ok(());
+    };
+
+    let mut name = std::env::var("USER").unwrap_or_default();
+    loop {
+        if terminal.interrupted() {
+            return Ok(());
+        }
+
+        let title = status_title(game);
+        terminal.draw(|f| {
+            draw(f, game, &config.visuals, &title, 0.0);
+            draw_popup(
+                f,
+                "New high score!",
+                vec![
+                    Spans::from(format!("#{rank} with {} points", entry.score)),
+                    Spans::from(""),
+                    Spans::from(format!("Name: {name}_")),
+                    Spans::from(""),
+                    Spans::from("Enter to save, Esc to skip"),
+                ],
+            );
+        })?;
+
+        if !event::poll(Duration::from_millis(100))? {
+            continue;
+        }
+        if let Event::Key(key) = event::read()? {
+            if key.kind == KeyEventKind::Release {
+                continue;
+            }
+            match key.code {
+                KeyCode::Enter => break,
+                KeyCode::Esc => return Ok(()),
+                KeyCode::Backspace => {
+                    name.pop();
                 }
-                lines.push(Spans::from(spans));
+                KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    return Ok(())
+                }
+                KeyCode::Char(c) if name.chars().count() < 20 => name.push(c),
+                _ => {}
             }
+        }
+    }
+
+    entry.name = name;
+    high_scores.insert(entry);
+    if let Err(err) = high_scores.save(&path) {
+        let message = format!("Could not save {}: {err}", path.display());
+        return notice(terminal, config, game, "High scores", &message);
+    }
+    Ok(())
+}
 
-            let paragraph = Paragraph::new(lines)
-                .block(block)
-                .alignment(Alignment::Left);
-            f.render_widget(paragraph, outer_area);
+// Show a message over the finished game until a key is pressed
+fn notice(
+    terminal: &mut TerminalGuard,
+    config: &Config,
+    game: &Game,
+    title: &str,
+    message: &str,
+) -> Result<(), Box<dyn Error>> {
+    while !terminal.interrupted() {
+        let status = status_title(game);
+        terminal.draw(|f| {
+            draw(f, game, &config.visuals, &status, 0.0);
+            draw_popup(
+                f,
+                title,
+                vec![
+                    Spans::from(message),
+                    Spans::from(""),
+                    Spans::from("Press any key to continue"),
+                ],
+            );
         })?;
 
-        // Input handling with non-blocking poll
-        if event::poll(Duration::from_millis(0))? {
+        if event::poll(Duration::from_millis(100))? {
+            if let Event::Key(key) = event::read()? {
+                if key.kind != KeyEventKind::Release {
+                    break;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+// Print every high-score table, optionally limited to one mode or board size
+fn list_scores(options: &ScoresOptions) -> Result<(), Box<dyn Error>> {
+    let path = HighScores::default_path().ok_or("cannot locate the data directory")?;
+    let high_scores = HighScores::load(&path)?;
+
+    let mut tables: Vec<(&str, u16, u16)> = high_scores
+        .entries()
+        .iter()
+        .map(|e| (e.mode.as_str(), e.width, e.height))
+        .filter(|&(mode, width, height)| {
+            options.mode.as_deref().is_none_or(|m| m == mode)
+                && options.size.is_none_or(|size| size == (width, height))
+        })
+        .collect();
+    tables.sort_unstable();
+    tables.dedup();
+
+    if tables.is_empty() {
+        println!("No high scores yet.");
+        return Ok(());
+    }
+
+    for (i, &(mode, width, height)) in tables.iter().enumerate() {
+        if i > 0 {
+            println!();
+        }
+        println!("{mode} {width}x{height}");
+        println!(
+            "  {:>2}  {:<20} {:>8}  {:<10}  {:>8}  Seed",
+            "#", "Name", "Score", "Date", "Time"
+        );
+        for (rank, e) in high_scores.table(mode, width, height).iter().enumerate() {
+            println!(
+                "  {:>2}  {:<20} {:>8}  {:<10}  {:>7.1}s  {}",
+                rank + 1,
+                e.name,
+                e.score,
+                scores::format_date(e.date),
+                e.duration.as_secs_f64(),
+                e.seed
+            );
+        }
+    }
+    Ok(())
+}
+
+fn watch_replay(
+    terminal: &mut TerminalGuard,
+    options: &ReplayOptions,
+    config: &Config,
+) -> Result<(), Box<dyn Error>> {
+    let replay =
+        Replay::load(&options.path).map_err(|err| format!("{}: {err}", options.path.display()))?;
+    let mut player = replay.player();
+    let mut speed = options.speed;
+    // Drawn the way it was recorded, where the terminal allows
+    let visuals = Visuals {
+        resolution: ui::fallback(replay.resolution),
+        ..config.visuals.clone()
+    };
+    let keymap = config.controls.keymap();
+    let mut clock = FixedTimestep::new();
+    let mut dirty = true;
+
+    while !terminal.interrupted() {
+        if dirty {
+            let game = player.game();
+            let state = if player.is_finished() {
+                " [finished]"
+            } else if clock.is_paused() {
+                " [paused]"
+            } else {
+                ""
+            };
+            let title = format!(
+                "Replay  {}  Tick: {}/{}  Speed: {}x{}",
+                status_title(game),
+                game.tick,
+                replay.ticks,
+                speed,
+                state
+            );
+            let progress = tick_progress(&visuals, &clock, game.tick_rate().div_f64(speed));
+            terminal.draw(|f| draw(f, game, &visuals, &title, progress))?;
+            dirty = false;
+        }
+
+        let step = player.game().tick_rate().div_f64(speed);
+        let animating = !player.is_finished() && is_animating(&visuals, &clock, player.game());
+        let timeout = if player.is_finished() {
+            IDLE_POLL
+        } else if animating {
+            clock
+                .until_next(step)
+                .map_or(FRAME_INTERVAL, |due| due.min(FRAME_INTERVAL))
+        } else {
+            clock
+                .until_next(step)
+                .map_or(IDLE_POLL, |due| due.min(IDLE_POLL))
+        };
+
+        // Space pauses, Right steps while paused, +/- change speed
+        if !event::poll(timeout)? {
+            dirty |= animating;
+        } else {
+            dirty = true;
             if let Event::Key(key) = event::read()? {
+                if keymap.action(&key) == Some(Action::Quit) {
+                    return Ok(());
+                }
                 match key.code {
-                    KeyCode::Left => {
-                        if game.player_x > 0 {
-                            game.player_x -= 1;
-                        }
+                    KeyCode::Char(' ') => clock.set_paused(!clock.is_paused()),
+                    KeyCode::Right | KeyCode::Char('.') if clock.is_paused() => player.step(),
+                    KeyCode::Char('+') | KeyCode::Char('=') => {
+                        speed = REPLAY_SPEEDS
+                            .iter()
+                            .copied()
+                            .find(|&s| s > speed)
+                            .unwrap_or(speed);
                     }
-                    KeyCode::Right => {
-                        if game.player_x < game.width.saturating_sub(1) {
-                            game.player_x += 1;
-                        }
+                    KeyCode::Char('-') => {
+                        speed = REPLAY_SPEEDS
+                            .iter()
+                            .rev()
+                            .copied()
+                            .find(|&s| s < speed)
+                            .unwrap_or(speed);
                     }
-                    KeyCode::Char('q') | KeyCode::Esc => break 'game_loop,
                     _ => {}
                 }
             }
         }
 
-        // Update game state based on tick rate
-        if last_tick.elapsed() >= TICK_RATE {
-            game.update();
-            if game.check_collision() {
-                break 'game_loop;
+        clock.advance();
+        let mut steps = 0;
+        while !player.is_finished() && clock.take_step(step) {
+            player.step();
+            dirty = true;
+            steps += 1;
+            if steps == MAX_CATCH_UP {
+                clock.discard_backlog();
             }
-            last_tick = Instant::now();
         }
     }
+    Ok(())
+}
 
-    // Clean up terminal
-    disable_raw_mode()?;
-    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
-    terminal.show_cursor()?;
-    println!("Game Over! Final Score: {}", game.score);
+// Load the config file and apply command-line overrides on top
+fn load_config(options: &GameOptions) -> Result<Config, Box<dyn Error>> {
+    let mut config = Config::load(options.config.as_deref())?;
+    let gameplay = &mut config.gameplay;
+    if let Some(curve) = options.curve {
+        gameplay.curve = curve;
+    }
+    if let Some(tick_rate_ms) = options.tick_rate_ms {
+        gameplay.tick_rate_ms = tick_rate_ms;
+        gameplay.min_tick_rate_ms = gameplay.min_tick_rate_ms.min(tick_rate_ms);
+    }
+    if let Some(probability) = options.spawn_probability {
+        gameplay.spawn_probability = probability;
+        gameplay.max_spawn_probability = gameplay.max_spawn_probability.max(probability);
+    }
+    if let Some(movement) = options.movement {
+        gameplay.movement = movement;
+    }
+    if let Some(zone_rows) = options.zone_rows {
+        gameplay.zone_rows = zone_rows;
+    }
+    if let Some(physics) = options.physics {
+        gameplay.physics = physics;
+    }
+    if let Some(lives) = options.lives {
+        gameplay.lives = lives;
+    }
+    if let Some(shields) = options.shields {
+        gameplay.shields = shields;
+    }
+    if let Some(probability) = options.powerup_probability {
+        gameplay.powerup_probability = probability;
+    }
+    if let Some(probability) = options.hazard_probability {
+        gameplay.hazard_probability = probability;
+    }
+    if let Some(probability) = options.wave_probability {
+        gameplay.wave_probability = probability;
+    }
+    if let Some(fair) = options.fair {
+        gameplay.fair = fair;
+    }
+    config
+        .validate()
+        .map_err(|message| format!("invalid command-line option: {message}"))?;
+    Ok(config)
+}
+
+fn run() -> Result<(), Box<dyn Error>> {
+    // Configuration is loaded before touching the terminal so errors print normally
+    match Command::parse(std::env::args().skip(1))? {
+        Command::Play(options) => {
+            let mut config = load_config(&options.game)?;
+            config.visuals.resolution = ui::fallback(config.visuals.resolution);
+            let mut terminal = TerminalGuard::new()?;
+            let result = play(&mut terminal, &options, &config);
+            drop(terminal);
+            let game = result?;
+            println!("Game Over! Final Score: {}", game.score);
+            println!("Seed: {} (replay with --seed {})", game.seed, game.seed);
+        }
+        Command::Replay(options) => {
+            let config = Config::load(options.config.as_deref())?;
+            let mut terminal = TerminalGuard::new()?;
+            let result = watch_replay(&mut terminal, &options, &config);
+            drop(terminal);
+            result?;
+        }
+        Command::Scores(options) => list_scores(&options)?,
+        Command::Bench(options) => headless::bench(&options, &load_config(&options.game)?)?,
+        Command::Simulate(options) => headless::simulate(&options, &load_config(&options.game)?)?,
+        Command::Help(usage) => println!("{usage}"),
+        Command::Version => println!("dodge {}", env!("CARGO_PKG_VERSION")),
+    }
 
     Ok(())
 }
 
+fn main() -> ExitCode {
+    // Print errors with Display rather than the Debug output `main` would use
+    match run() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("dodge: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
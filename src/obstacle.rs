@@ -0,0 +1,253 @@
+use rand::Rng;
+
+use crate::physics::{Fixed, CELL};
+
+/// How an obstacle moves and what it looks like.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BlockKind {
+    // One row per tick, straight down
+    #[default]
+    Normal,
+    // Two rows per tick
+    Fast,
+    // One row every other tick
+    Slow,
+    // Moves diagonally, bouncing off the side walls
+    Bouncer,
+    // Sways one column left and right as it falls
+    ZigZag,
+    // Several cells wide
+    Slab,
+    // Starts slow and speeds up the longer it falls
+    Accelerating,
+}
+
+impl BlockKind {
+    pub const ALL: [BlockKind; 7] = [
+        BlockKind::Normal,
+        BlockKind::Fast,
+        BlockKind::Slow,
+        BlockKind::Bouncer,
+        BlockKind::ZigZag,
+        BlockKind::Slab,
+        BlockKind::Accelerating,
+    ];
+
+    // Relative spawn weight at the given difficulty intensity (0.0 to 1.0);
+    // the trickier kinds only appear as the run heats up
+    fn weight(self, intensity: f64) -> f64 {
+        match self {
+            BlockKind::Normal => 10.0,
+            BlockKind::Slow => 2.0,
+            BlockKind::Slab => 1.0 + 2.0 * intensity,
+            BlockKind::Fast => 4.0 * intensity,
+            BlockKind::Bouncer | BlockKind::ZigZag => 3.0 * intensity,
+            BlockKind::Accelerating => 2.0 * intensity,
+        }
+    }
+
+    // Rows per tick a freshly spawned block falls
+    fn initial_speed(self) -> Fixed {
+        match self {
+            BlockKind::Normal | BlockKind::Bouncer | BlockKind::ZigZag | BlockKind::Slab => CELL,
+            BlockKind::Fast => 2 * CELL,
+            BlockKind::Slow | BlockKind::Accelerating => CELL / 2,
+        }
+    }
+
+    // Pick a kind at random, weighted by difficulty
+    pub(crate) fn choose<R: Rng>(rng: &mut R, intensity: f64) -> Self {
+        let total: f64 = Self::ALL.iter().map(|kind| kind.weight(intensity)).sum();
+        let mut roll = rng.gen_range(0.0..total);
+        for kind in Self::ALL {
+            roll -= kind.weight(intensity);
+            if roll < 0.0 {
+                return kind;
+            }
+        }
+        BlockKind::Normal
+    }
+}
+
+// The cells a block passed through in one step: straight down its old
+// columns, then across into its new ones in the row it stopped in
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Sweep {
+    from: (u16, u16),
+    to: (u16, u16),
+    width: u16,
+}
+
+impl Sweep {
+    // Whether the block passed through the cell. A block swapping places
+    // with the player starts in the player's cell, so that counts too.
+    pub fn covers(&self, (x, y): (u16, u16)) -> bool {
+        let (left, right) = self.columns();
+        let fell = (self.from.0..self.from.0.saturating_add(self.width)).contains(&x)
+            && (self.from.1..=self.to.1).contains(&y);
+        let slid = y == self.to.1 && (left..right).contains(&x);
+        fell || slid
+    }
+
+    // Every column touched, right end exclusive
+    pub fn columns(&self) -> (u16, u16) {
+        let left = self.from.0.min(self.to.0);
+        let right = self.from.0.max(self.to.0).saturating_add(self.width);
+        (left, right)
+    }
+
+    // First and last row touched
+    pub fn rows(&self) -> (u16, u16) {
+        (self.from.1, self.to.1)
+    }
+}
+
+// Widest slab the spawner creates
+pub const MAX_SLAB_WIDTH: u16 = 6;
+
+/// An obstacle falling down the board. `x` is its leftmost column and `y`
+/// the row it is in; `offset` is how far down that row it has got.
+#[derive(Debug, Clone)]
+pub struct FallingBlock {
+    pub x: u16,
+    pub y: u16,
+    pub kind: BlockKind,
+    pub width: u16,
+    pub dx: i8,        // horizontal direction for bouncers and zig-zags
+    pub age: u32,      // ticks since spawning
+    pub speed: Fixed,  // rows fallen per tick
+    pub offset: Fixed, // 0 at the top of the row, CELL at the bottom
+}
+
+impl FallingBlock {
+    // A plain one-cell block
+    pub fn new(x: u16, y: u16) -> Self {
+        Self::with_kind(x, y, BlockKind::Normal)
+    }
+
+    pub fn with_kind(x: u16, y: u16, kind: BlockKind) -> Self {
+        Self {
+            x,
+            y,
+            kind,
+            width: 1,
+            dx: 1,
+            age: 0,
+            speed: kind.initial_speed(),
+            offset: CELL / 2,
+        }
+    }
+
+    // Whether the block covers column `x`
+    pub fn covers(&self, x: u16) -> bool {
+        (self.x..self.x.saturating_add(self.width)).contains(&x)
+    }
+
+    // Advance one tick on a board of the given width, returning the cells
+    // swept on the way so collisions can't be skipped
+    pub(crate) fn step(&mut self, board_width: u16) -> Sweep {
+        let (from_x, from_y) = (self.x, self.y);
+        // Accelerating blocks double their speed every six ticks, up to
+        // three rows per tick
+        if self.kind == BlockKind::Accelerating && self.age > 0 && self.age.is_multiple_of(6) {
+            self.speed = (self.speed * 2).min(3 * CELL);
+        }
+        let fallen = self.offset + self.speed.max(0);
+        self.y = self.y.saturating_add((fallen / CELL) as u16);
+        self.offset = fallen % CELL;
+
+        let max_x = board_width.saturating_sub(self.width);
+        match self.kind {
+            BlockKind::Bouncer => {
+                if (self.dx < 0 && self.x == 0) || (self.dx > 0 && self.x >= max_x) {
+                    self.dx = -self.dx;
+                }
+                self.x = self.x.saturating_add_signed(self.dx.into()).min(max_x);
+            }
+            // Alternate sides every two ticks, turning early at a wall
+            BlockKind::ZigZag => {
+                if self.age % 2 == 1 {
+                    self.dx = -self.dx;
+                }
+                if (self.dx < 0 && self.x == 0) || (self.dx > 0 && self.x >= max_x) {
+                    self.dx = -self.dx;
+                }
+                self.x = self.x.saturating_add_signed(self.dx.into()).min(max_x);
+            }
+            _ => {}
+        }
+        self.age += 1;
+
+        Sweep {
+            from: (from_x, from_y),
+            to: (self.x, self.y),
+            width: self.width,
+        }
+    }
+
+    // The row to draw the block in part way (0.0 to 1.0) through the next
+    // tick: the next row once it is halfway there, so even a block falling a
+    // row per tick moves between ticks. It never gets ahead of where the tick
+    // will leave it.
+    pub fn row_at(&self, progress: f64) -> u16 {
+        let ahead = self.offset + (f64::from(self.speed) * progress) as Fixed;
+        let rows = ahead.min(self.offset + self.speed.max(0)) / CELL;
+        self.y.saturating_add(rows.max(0) as u16)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bouncer(x: u16, y: u16, dx: i8) -> FallingBlock {
+        let mut block = FallingBlock::with_kind(x, y, BlockKind::Bouncer);
+        block.dx = dx;
+        block
+    }
+
+    #[test]
+    fn diagonal_sweep_skips_the_corner_it_cuts() {
+        let sweep = bouncer(5, 10, 1).step(20);
+        assert_eq!(sweep.rows(), (10, 11));
+        assert!(sweep.covers((5, 10)));
+        assert!(sweep.covers((5, 11)));
+        assert!(sweep.covers((6, 11)));
+        assert!(!sweep.covers((6, 10)));
+    }
+
+    #[test]
+    fn sweep_covers_every_row_fallen() {
+        let mut block = FallingBlock::with_kind(3, 0, BlockKind::Fast);
+        let sweep = block.step(10);
+        assert_eq!(block.y, 2);
+        for y in 0..=2 {
+            assert!(sweep.covers((3, y)));
+        }
+        assert!(!sweep.covers((3, 3)));
+        assert!(!sweep.covers((4, 1)));
+    }
+
+    #[test]
+    fn wide_block_sweeps_all_its_columns() {
+        let mut slab = FallingBlock::with_kind(2, 4, BlockKind::Slab);
+        slab.width = 3;
+        let sweep = slab.step(10);
+        for x in 2..5 {
+            assert!(sweep.covers((x, 5)));
+        }
+        assert!(!sweep.covers((5, 5)));
+    }
+
+    #[test]
+    fn block_is_drawn_in_the_next_row_from_halfway() {
+        let block = FallingBlock::new(3, 4);
+        assert_eq!(block.row_at(0.0), 4);
+        assert_eq!(block.row_at(0.4), 4);
+        assert_eq!(block.row_at(0.5), 5);
+        assert_eq!(block.row_at(1.0), 5);
+
+        let slow = FallingBlock::with_kind(3, 4, BlockKind::Slow);
+        assert_eq!(slow.row_at(0.9), 4);
+    }
+}
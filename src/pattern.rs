@@ -0,0 +1,228 @@
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::{Arc, OnceLock};
+
+use crate::replay::parse_field;
+
+// Columns the player is assumed to cover per tick when checking that a wave
+// can be passed; consecutive gaps are pulled within this reach of each other
+pub const WAVE_REACH: u16 = 1;
+
+// Most rows a wave may have, so a wave file or a replay embedding one can't
+// ask for unbounded memory
+pub const MAX_WAVE_ROWS: usize = 200;
+
+// Largest weight a wave may have, keeping the sum of every weight in range
+pub const MAX_WEIGHT: u32 = 1000;
+
+const BUILTIN: &str = include_str!("../waves/default.waves");
+
+/// One row of a wave as it enters the board.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PatternRow {
+    // Nothing spawns
+    Blank,
+    // A full-width wall with one opening. `center` is a fraction of the board
+    // width, `width` a number of cells.
+    Gap { center: f64, width: u16 },
+}
+
+/// A named, hand-authored sequence of rows the spawner can drop as a wave.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pattern {
+    pub name: String,
+    pub weight: u32,    // relative chance of being picked
+    pub min_level: u32, // earliest level the wave appears at
+    pub mirror: bool,   // may be flipped left to right
+    pub rows: Vec<PatternRow>,
+}
+
+#[derive(Debug)]
+pub struct PatternError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for PatternError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl Error for PatternError {}
+
+impl Pattern {
+    // The waves shipped with the game
+    pub fn builtin() -> Arc<Vec<Pattern>> {
+        static PATTERNS: OnceLock<Arc<Vec<Pattern>>> = OnceLock::new();
+        PATTERNS
+            .get_or_init(|| {
+                Arc::new(Pattern::parse_all(BUILTIN).expect("built-in waves are valid"))
+            })
+            .clone()
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Vec<Pattern>, Box<dyn Error>> {
+        Ok(Self::parse_all(&fs::read_to_string(path)?)?)
+    }
+
+    // Parse a wave file: blocks of `wave <name>` followed by option and row
+    // lines, each closed by `end`
+    pub fn parse_all(text: &str) -> Result<Vec<Pattern>, PatternError> {
+        let mut patterns = Vec::new();
+        let mut current: Option<Pattern> = None;
+
+        for (index, line) in text.lines().enumerate() {
+            let err = |message: String| PatternError {
+                line: index + 1,
+                message,
+            };
+            let line = line.split('#').next().unwrap_or_default();
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.is_empty() {
+                continue;
+            }
+
+            let Some(pattern) = &mut current else {
+                match fields.as_slice() {
+                    ["wave", name] => {
+                        current = Some(Pattern {
+                            name: name.to_string(),
+                            weight: 1,
+                            min_level: 1,
+                            mirror: false,
+                            rows: Vec::new(),
+                        })
+                    }
+                    _ => return Err(err("expected `wave <name>`".into())),
+                }
+                continue;
+            };
+
+            match fields.as_slice() {
+                ["weight", weight] => {
+                    pattern.weight = parse_field(weight, "weight").map_err(err)?;
+                    if pattern.weight > MAX_WEIGHT {
+                        return Err(err(format!(
+                            "weight must be at most {MAX_WEIGHT}, got {}",
+                            pattern.weight
+                        )));
+                    }
+                }
+                ["level", level] => pattern.min_level = parse_field(level, "level").map_err(err)?,
+                ["mirror"] => pattern.mirror = true,
+                ["blank" | "gap", ..] if pattern.rows.len() >= MAX_WAVE_ROWS => {
+                    return Err(err(format!(
+                        "wave `{}` has more than {MAX_WAVE_ROWS} rows",
+                        pattern.name
+                    )));
+                }
+                ["blank"] => pattern.rows.push(PatternRow::Blank),
+                ["blank", count] => {
+                    let count: usize = parse_field(count, "blank count").map_err(err)?;
+                    if count > MAX_WAVE_ROWS - pattern.rows.len() {
+                        return Err(err(format!(
+                            "wave `{}` has more than {MAX_WAVE_ROWS} rows",
+                            pattern.name
+                        )));
+                    }
+                    pattern.rows.extend((0..count).map(|_| PatternRow::Blank));
+                }
+                ["gap", center, width] => {
+                    let center: f64 = parse_field(center, "gap center").map_err(err)?;
+                    let width: u16 = parse_field(width, "gap width").map_err(err)?;
+                    if !(0.0..=1.0).contains(&center) {
+                        return Err(err(format!(
+                            "gap center must be between 0 and 1, got {center}"
+                        )));
+                    }
+                    if width == 0 {
+                        return Err(err("gap width must be at least 1".into()));
+                    }
+                    pattern.rows.push(PatternRow::Gap { center, width });
+                }
+                ["end"] => {
+                    let pattern = current.take().expect("inside a wave");
+                    if pattern.rows.is_empty() {
+                        return Err(err(format!("wave `{}` has no rows", pattern.name)));
+                    }
+                    patterns.push(pattern);
+                }
+                _ => return Err(err(format!("unrecognized line `{}`", line.trim()))),
+            }
+        }
+
+        if let Some(pattern) = current {
+            return Err(PatternError {
+                line: text.lines().count(),
+                message: format!("wave `{}` is missing `end`", pattern.name),
+            });
+        }
+        Ok(patterns)
+    }
+
+    // Write the pattern back out in the format `parse_all` reads
+    pub fn write<W: Write>(&self, mut w: W) -> io::Result<()> {
+        writeln!(w, "wave {}", self.name)?;
+        writeln!(w, "weight {}", self.weight)?;
+        writeln!(w, "level {}", self.min_level)?;
+        if self.mirror {
+            writeln!(w, "mirror")?;
+        }
+        for row in &self.rows {
+            match row {
+                PatternRow::Blank => writeln!(w, "blank")?,
+                PatternRow::Gap { center, width } => writeln!(w, "gap {center} {width}")?,
+            }
+        }
+        writeln!(w, "end")
+    }
+
+    // Lay the pattern out on a board of the given width as the columns left
+    // open on each row (`None` for blank rows). Gaps are shifted where needed
+    // so each one is within reach of the last, starting from a player in
+    // `column` who has `lead` ticks before the first row arrives.
+    pub fn resolve(
+        &self,
+        board_width: u16,
+        mirrored: bool,
+        column: u16,
+        lead: u16,
+    ) -> Vec<Option<(u16, u16)>> {
+        let mut rows = Vec::with_capacity(self.rows.len());
+        let (mut prev_left, mut prev_right) = (column, column.saturating_add(1));
+        let mut since_previous = lead.saturating_sub(1);
+
+        for row in &self.rows {
+            since_previous = since_previous.saturating_add(1);
+            let PatternRow::Gap { center, width } = *row else {
+                rows.push(None);
+                continue;
+            };
+            if board_width == 0 {
+                rows.push(None);
+                continue;
+            }
+
+            let center = if mirrored { 1.0 - center } else { center };
+            let width = width.min(board_width);
+            let max_left = board_width - width;
+            let ideal = (center * f64::from(board_width.saturating_sub(1))).round() as u16;
+            let mut left = ideal.saturating_sub(width / 2).min(max_left);
+
+            // The new gap must overlap [prev_left - reach, prev_right + reach)
+            let reach = since_previous.saturating_mul(WAVE_REACH);
+            let lowest = prev_left.saturating_sub(reach).saturating_sub(width - 1);
+            let highest = prev_right.saturating_add(reach).saturating_sub(1);
+            left = left.clamp(lowest.min(max_left), highest.min(max_left));
+
+            rows.push(Some((left, left + width)));
+            (prev_left, prev_right) = (left, left + width);
+            since_previous = 0;
+        }
+        rows
+    }
+}
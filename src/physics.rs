@@ -0,0 +1,51 @@
+/// A distance or speed in 1/256ths of a board cell. Integer math keeps runs
+/// identical on every machine, which replays depend on.
+pub type Fixed = i32;
+
+// One board cell
+pub const CELL: Fixed = 256;
+
+// Speed the player gains each tick a direction is held, in cells per tick
+pub const PLAYER_ACCELERATION: Fixed = CELL / 2;
+
+pub const PLAYER_MAX_SPEED: Fixed = 2 * CELL;
+
+// A coasting player slower than this stops
+const STOP_SPEED: Fixed = CELL / 16;
+
+// Push a speed in the held direction (-1, 0 or 1). Pushing against the
+// current motion turns around on the spot rather than braking first.
+pub fn accelerate(speed: Fixed, direction: i8) -> Fixed {
+    let direction = Fixed::from(direction.signum());
+    if direction == 0 {
+        speed
+    } else if speed * direction <= 0 {
+        direction * PLAYER_ACCELERATION
+    } else {
+        (speed + direction * PLAYER_ACCELERATION).clamp(-PLAYER_MAX_SPEED, PLAYER_MAX_SPEED)
+    }
+}
+
+// Friction halves a coasting player's speed every tick
+pub fn coast(speed: Fixed) -> Fixed {
+    let speed = speed / 2;
+    if speed.abs() < STOP_SPEED {
+        0
+    } else {
+        speed
+    }
+}
+
+// Cell borders crossed moving `distance` from `offset` across a cell (0 at
+// its left or top edge, CELL at the other), negative when moving left or up.
+// A border is crossed on reaching it, whichever way the move goes.
+pub fn cells_crossed(offset: Fixed, distance: Fixed) -> i32 {
+    let to = offset + distance;
+    if distance > 0 && to >= CELL {
+        to / CELL
+    } else if distance < 0 && to <= 0 {
+        -(-to / CELL + 1)
+    } else {
+        0
+    }
+}
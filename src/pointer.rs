@@ -0,0 +1,79 @@
+use crossterm::event::{MouseButton, MouseEventKind};
+use dodge::{Game, Input, DASH_CELLS};
+
+use crate::controls::Mouse;
+
+// Cells per tick the player walks toward the pointer or a click
+pub const FOLLOW_SPEED: u16 = 1;
+
+// Steers the player toward a board cell picked with the mouse, emitting the
+// same moves as the keyboard so replays record them like any other input
+pub struct Pointer {
+    mode: Mouse,
+    target: Option<(u16, u16)>,
+}
+
+impl Pointer {
+    pub fn new(mode: Mouse) -> Self {
+        Self { mode, target: None }
+    }
+
+    // Note a mouse event over the given board cell (None when it is off the
+    // board)
+    pub fn event(&mut self, kind: MouseEventKind, cell: Option<(u16, u16)>) {
+        let Some(cell) = cell else {
+            return;
+        };
+        let aims = match self.mode {
+            Mouse::Off => false,
+            Mouse::Follow => matches!(
+                kind,
+                MouseEventKind::Moved
+                    | MouseEventKind::Down(MouseButton::Left)
+                    | MouseEventKind::Drag(MouseButton::Left)
+            ),
+            Mouse::Click => kind == MouseEventKind::Down(MouseButton::Left),
+        };
+        if aims {
+            self.target = Some(cell);
+        }
+    }
+
+    // Stop steering, so keys aren't fought until the mouse moves again
+    pub fn release(&mut self) {
+        self.target = None;
+    }
+
+    // Moves toward the target to make this tick, walking at FOLLOW_SPEED
+    // along each axis. After a click the player also dashes when the dash is
+    // ready and wouldn't overshoot, so clicks share the keyboard's cooldown.
+    // A click's target is dropped once reached.
+    pub fn moves(&mut self, game: &Game) -> Vec<Input> {
+        let Some((x, y)) = self.target else {
+            return Vec::new();
+        };
+        let (top, bottom) = game.player_rows();
+        let y = y.clamp(top, bottom);
+        let mut dash = self.mode == Mouse::Click && game.dash_cooldown() == 0;
+
+        let mut moves = Vec::new();
+        for (from, to, back, forward) in [
+            (game.player_x, x, Input::Left, Input::Right),
+            (game.player_y, y, Input::Up, Input::Down),
+        ] {
+            let input = if to < from { back } else { forward };
+            let distance = from.abs_diff(to);
+            let cells = distance.min(FOLLOW_SPEED);
+            moves.extend(std::iter::repeat_n(input, usize::from(cells)));
+            // The walk just faced the player this way, which a dash follows
+            if dash && cells > 0 && distance >= cells + DASH_CELLS {
+                moves.push(Input::Dash);
+                dash = false;
+            }
+        }
+        if moves.is_empty() && self.mode == Mouse::Click {
+            self.target = None;
+        }
+        moves
+    }
+}
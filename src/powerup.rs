@@ -0,0 +1,68 @@
+use std::fmt;
+
+/// A collectible item's effect.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PowerUp {
+    // Ticks last twice as long
+    SlowTime,
+    // Absorbs the next hit
+    Shield,
+    // Fewer and smaller blocks
+    Shrink,
+    // Double points per tick
+    Multiplier,
+    // Clears every block on the board
+    Bomb,
+    // Pulls falling items towards the player's column
+    Magnet,
+}
+
+impl PowerUp {
+    pub const ALL: [PowerUp; 6] = [
+        PowerUp::SlowTime,
+        PowerUp::Shield,
+        PowerUp::Shrink,
+        PowerUp::Multiplier,
+        PowerUp::Bomb,
+        PowerUp::Magnet,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            PowerUp::SlowTime => "slow",
+            PowerUp::Shield => "shield",
+            PowerUp::Shrink => "shrink",
+            PowerUp::Multiplier => "x2",
+            PowerUp::Bomb => "bomb",
+            PowerUp::Magnet => "magnet",
+        }
+    }
+
+    // Ticks the effect lasts once collected; 0 for effects applied instantly
+    pub fn duration(self) -> u64 {
+        match self {
+            PowerUp::SlowTime => 50,
+            PowerUp::Shrink => 75,
+            PowerUp::Multiplier | PowerUp::Magnet => 100,
+            PowerUp::Shield | PowerUp::Bomb => 0,
+        }
+    }
+
+    pub(crate) fn index(self) -> usize {
+        self as usize
+    }
+}
+
+impl fmt::Display for PowerUp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// A power-up falling towards the player.
+#[derive(Debug, Clone)]
+pub struct Item {
+    pub x: u16,
+    pub y: u16,
+    pub kind: PowerUp,
+}
@@ -0,0 +1,340 @@
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+use crate::game::{Game, Input};
+use crate::pattern::Pattern;
+use crate::resolution::Resolution;
+use crate::rules::Rules;
+
+// Bumped whenever the on-disk layout changes
+pub const FORMAT_VERSION: u32 = 1;
+
+const MAGIC: &str = "dodge-replay";
+
+/// Something that changed the simulation between ticks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventKind {
+    Input(Input),
+    Resize { width: u16, height: u16 },
+}
+
+/// An event together with the tick it arrived on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReplayEvent {
+    pub tick: u64,
+    pub kind: EventKind,
+}
+
+/// Everything needed to re-simulate a run: the starting conditions and
+/// every input in the order it was applied.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Replay {
+    pub seed: u64,
+    pub width: u16,
+    pub height: u16,
+    pub ticks: u64, // length of the run in ticks
+    pub rules: Rules,
+    pub patterns: Vec<Pattern>, // waves in play; empty when waves are off
+    pub resolution: Resolution, // how the run was drawn, so it plays back the same
+    pub events: Vec<ReplayEvent>,
+}
+
+#[derive(Debug)]
+pub enum ReplayError {
+    Io(io::Error),
+    UnsupportedVersion(u32),
+    Parse { line: usize, message: String },
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayError::Io(err) => write!(f, "{err}"),
+            ReplayError::UnsupportedVersion(version) => write!(
+                f,
+                "unsupported replay version {version} (expected {FORMAT_VERSION})"
+            ),
+            ReplayError::Parse { line, message } => write!(f, "line {line}: {message}"),
+        }
+    }
+}
+
+impl Error for ReplayError {}
+
+impl From<io::Error> for ReplayError {
+    fn from(err: io::Error) -> Self {
+        ReplayError::Io(err)
+    }
+}
+
+impl Replay {
+    // Start an empty recording for a freshly created game
+    pub fn new(game: &Game) -> Self {
+        Self {
+            seed: game.seed,
+            width: game.width,
+            height: game.height,
+            ticks: 0,
+            rules: game.rules,
+            patterns: if game.rules.wave_probability > 0.0 {
+                game.patterns().to_vec()
+            } else {
+                Vec::new()
+            },
+            resolution: Resolution::default(),
+            events: Vec::new(),
+        }
+    }
+
+    pub fn record(&mut self, tick: u64, input: Input) {
+        self.events.push(ReplayEvent {
+            tick,
+            kind: EventKind::Input(input),
+        });
+    }
+
+    pub fn record_resize(&mut self, tick: u64, width: u16, height: u16) {
+        self.events.push(ReplayEvent {
+            tick,
+            kind: EventKind::Resize { width, height },
+        });
+    }
+
+    // Mark the run as ended after the given number of ticks
+    pub fn finish(&mut self, ticks: u64) {
+        self.ticks = ticks;
+    }
+
+    // Create the game this replay starts from
+    pub fn new_game(&self) -> Game {
+        Game::new(self.width, self.height, self.seed)
+            .with_rules(self.rules)
+            .with_patterns(self.patterns.clone())
+    }
+
+    pub fn player(&self) -> ReplayPlayer<'_> {
+        ReplayPlayer {
+            replay: self,
+            game: self.new_game(),
+            next_event: 0,
+            finished: false,
+        }
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write(&mut writer)?;
+        writer.flush()
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ReplayError> {
+        Self::read(BufReader::new(File::open(path)?))
+    }
+
+    pub fn write<W: Write>(&self, mut w: W) -> io::Result<()> {
+        writeln!(w, "{MAGIC} {FORMAT_VERSION}")?;
+        writeln!(w, "seed {}", self.seed)?;
+        writeln!(w, "size {} {}", self.width, self.height)?;
+        writeln!(w, "ticks {}", self.ticks)?;
+        writeln!(w, "resolution {}", self.resolution)?;
+        self.rules.write(&mut w)?;
+        // Wave definitions are embedded so custom wave files aren't needed to replay
+        if !self.patterns.is_empty() {
+            writeln!(w, "patterns")?;
+            for pattern in &self.patterns {
+                pattern.write(&mut w)?;
+            }
+            writeln!(w, "end patterns")?;
+        }
+        for event in &self.events {
+            match event.kind {
+                EventKind::Input(input) => writeln!(w, "{} {}", event.tick, input.name())?,
+                EventKind::Resize { width, height } => {
+                    writeln!(w, "{} resize {width} {height}", event.tick)?
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn read<R: BufRead>(r: R) -> Result<Self, ReplayError> {
+        let mut replay = Replay {
+            seed: 0,
+            width: 0,
+            height: 0,
+            ticks: 0,
+            // Header lines missing from older replays keep their original behaviour
+            rules: Rules::legacy(),
+            patterns: Vec::new(),
+            resolution: Resolution::default(),
+            events: Vec::new(),
+        };
+        let mut seen_header = false;
+
+        let mut lines = r.lines().enumerate();
+        while let Some((index, line)) = lines.next() {
+            let line = line?;
+            let number = index + 1;
+            let err = |message: String| ReplayError::Parse {
+                line: number,
+                message,
+            };
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.is_empty() || fields[0].starts_with('#') {
+                continue;
+            }
+
+            if !seen_header {
+                if fields.len() != 2 || fields[0] != MAGIC {
+                    return Err(err(format!("expected `{MAGIC} <version>` header")));
+                }
+                let version = parse_field(fields[1], "version").map_err(err)?;
+                if version != FORMAT_VERSION {
+                    return Err(ReplayError::UnsupportedVersion(version));
+                }
+                seen_header = true;
+                continue;
+            }
+
+            if replay.rules.parse_line(&fields).map_err(err)? {
+                continue;
+            }
+            match fields.as_slice() {
+                ["seed", seed] => replay.seed = parse_field(seed, "seed").map_err(err)?,
+                ["size", width, height] => {
+                    replay.width = parse_field(width, "width").map_err(err)?;
+                    replay.height = parse_field(height, "height").map_err(err)?;
+                }
+                ["ticks", ticks] => replay.ticks = parse_field(ticks, "ticks").map_err(err)?,
+                ["resolution", name] => {
+                    replay.resolution = Resolution::from_name(name)
+                        .ok_or_else(|| err(format!("unknown resolution `{name}`")))?;
+                }
+                ["patterns"] => {
+                    let mut text = String::new();
+                    loop {
+                        let Some((_, line)) = lines.next() else {
+                            return Err(err("`patterns` is missing `end patterns`".into()));
+                        };
+                        let line = line?;
+                        if line.trim() == "end patterns" {
+                            break;
+                        }
+                        text.push_str(&line);
+                        text.push('\n');
+                    }
+                    replay.patterns =
+                        Pattern::parse_all(&text).map_err(|e| ReplayError::Parse {
+                            line: number + e.line,
+                            message: e.message,
+                        })?;
+                }
+                [tick, rest @ ..] => {
+                    let tick = parse_field(tick, "tick").map_err(err)?;
+                    let kind = match rest {
+                        ["resize", width, height] => EventKind::Resize {
+                            width: parse_field(width, "width").map_err(err)?,
+                            height: parse_field(height, "height").map_err(err)?,
+                        },
+                        [input] => EventKind::Input(
+                            Input::from_name(input)
+                                .ok_or_else(|| err(format!("unknown input `{input}`")))?,
+                        ),
+                        _ => return Err(err(format!("unrecognized line `{line}`"))),
+                    };
+                    if replay.events.last().is_some_and(|last| last.tick > tick) {
+                        return Err(err("events are out of order".into()));
+                    }
+                    replay.events.push(ReplayEvent { tick, kind });
+                }
+                _ => return Err(err(format!("unrecognized line `{line}`"))),
+            }
+        }
+
+        if !seen_header {
+            return Err(ReplayError::Parse {
+                line: 1,
+                message: "empty replay file".into(),
+            });
+        }
+        Ok(replay)
+    }
+}
+
+pub(crate) fn parse_field<T: std::str::FromStr>(value: &str, name: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("invalid {name} `{value}`"))
+}
+
+/// Re-simulates a replay one tick at a time.
+pub struct ReplayPlayer<'a> {
+    replay: &'a Replay,
+    game: Game,
+    next_event: usize,
+    finished: bool,
+}
+
+impl ReplayPlayer<'_> {
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    // Apply the inputs recorded for the current tick, then advance one tick
+    pub fn step(&mut self) {
+        if self.finished {
+            return;
+        }
+
+        while let Some(event) = self.replay.events.get(self.next_event) {
+            if event.tick != self.game.tick {
+                break;
+            }
+            match event.kind {
+                EventKind::Input(input) => self.game.handle_input(input),
+                EventKind::Resize { width, height } => self.game.resize(width, height),
+            }
+            self.next_event += 1;
+        }
+
+        if self.game.game_over || self.game.tick >= self.replay.ticks {
+            self.finished = true;
+            return;
+        }
+
+        self.game.update();
+        if self.game.game_over {
+            self.finished = true;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn write_then_read_gives_the_same_replay() {
+        let game = Game::new(30, 12, 42);
+        let mut replay = Replay::new(&game);
+        assert!(!replay.patterns.is_empty());
+        replay.resolution = Resolution::Braille;
+        replay.record(0, Input::Left);
+        replay.record(3, Input::Dash);
+        replay.record_resize(5, 40, 16);
+        replay.record(5, Input::Up);
+        replay.finish(90);
+
+        let mut bytes = Vec::new();
+        replay.write(&mut bytes).unwrap();
+        let read = Replay::read(bytes.as_slice()).unwrap();
+        assert_eq!(read, replay);
+    }
+}
@@ -0,0 +1,62 @@
+use std::fmt;
+
+/// How many board cells are drawn in one terminal cell. Board coordinates are
+/// always in board cells, so finer resolutions make for bigger boards on the
+/// same terminal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Resolution {
+    // One board cell per character, drawn with text glyphs
+    #[default]
+    Ascii,
+    // Two board rows per character, drawn with ▀ and ▄
+    HalfBlock,
+    // Two columns by four rows per character, drawn as braille dots
+    Braille,
+}
+
+impl Resolution {
+    pub const ALL: [Resolution; 3] = [
+        Resolution::Ascii,
+        Resolution::HalfBlock,
+        Resolution::Braille,
+    ];
+
+    // Board columns and rows per terminal cell
+    pub fn scale(self) -> (u16, u16) {
+        match self {
+            Resolution::Ascii => (1, 1),
+            Resolution::HalfBlock => (1, 2),
+            Resolution::Braille => (2, 4),
+        }
+    }
+
+    // The board that fills the given number of terminal columns and rows
+    pub fn board_size(self, (columns, rows): (u16, u16)) -> (u16, u16) {
+        let (sx, sy) = self.scale();
+        (columns.saturating_mul(sx), rows.saturating_mul(sy))
+    }
+
+    // Terminal columns and rows needed to show a board
+    pub fn screen_size(self, (width, height): (u16, u16)) -> (u16, u16) {
+        let (sx, sy) = self.scale();
+        (width.div_ceil(sx), height.div_ceil(sy))
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Resolution::Ascii => "ascii",
+            Resolution::HalfBlock => "half",
+            Resolution::Braille => "braille",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|r| r.name() == name)
+    }
+}
+
+impl fmt::Display for Resolution {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
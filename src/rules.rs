@@ -0,0 +1,321 @@
+use std::fmt;
+use std::io::{self, Write};
+use std::time::Duration;
+
+use crate::difficulty::{Curve, Difficulty};
+use crate::replay::parse_field as parse;
+use crate::resolution::Resolution;
+
+// Rows the player may use in zone mode unless configured otherwise
+pub const DEFAULT_ZONE_ROWS: u16 = 5;
+
+// Ticks the player can't be hit for after losing a life or a shield
+pub const INVULNERABLE_TICKS: u64 = 15;
+
+// Chance per tick of a power-up appearing on the top row
+pub const POWERUP_PROBABILITY: f64 = 0.02;
+
+// Chance per tick of a telegraphed column strike or row laser
+pub const HAZARD_PROBABILITY: f64 = 0.01;
+
+// Chance per tick, outside a wave, of the spawner starting an authored wave
+pub const WAVE_PROBABILITY: f64 = 0.01;
+
+/// Where the player is allowed to move.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Movement {
+    // Left and right only, one row above the bottom
+    #[default]
+    Horizontal,
+    // Anywhere within this many rows at the bottom of the arena
+    Zone(u16),
+    // Anywhere in the arena
+    Free,
+}
+
+impl Movement {
+    // First and last row the player may occupy on a board of the given height
+    pub fn rows(self, height: u16) -> (u16, u16) {
+        let bottom = height.saturating_sub(1);
+        match self {
+            Movement::Horizontal => {
+                let row = height.saturating_sub(2);
+                (row, row)
+            }
+            Movement::Zone(rows) => (height.saturating_sub(rows.max(1)), bottom),
+            Movement::Free => (0, bottom),
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Movement::Horizontal => "horizontal",
+            Movement::Zone(_) => "zone",
+            Movement::Free => "free",
+        }
+    }
+
+    // Zone mode gets the default number of rows
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "horizontal" => Some(Movement::Horizontal),
+            "zone" => Some(Movement::Zone(DEFAULT_ZONE_ROWS)),
+            "free" => Some(Movement::Free),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Movement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Movement::Zone(rows) => write!(f, "zone {rows}"),
+            _ => f.write_str(self.name()),
+        }
+    }
+}
+
+/// How key presses move the player.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Physics {
+    // Each press moves one cell straight away
+    #[default]
+    Step,
+    // Presses push the player, who speeds up while a direction is held and
+    // slides to a stop when it's let go
+    Inertia,
+}
+
+impl Physics {
+    pub fn name(self) -> &'static str {
+        match self {
+            Physics::Step => "step",
+            Physics::Inertia => "inertia",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        [Physics::Step, Physics::Inertia]
+            .into_iter()
+            .find(|physics| physics.name() == name)
+    }
+}
+
+impl fmt::Display for Physics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Every setting that changes how a run plays out. Two games with the same
+/// rules, seed, board size and inputs are identical.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rules {
+    pub difficulty: Difficulty,
+    pub movement: Movement,
+    pub physics: Physics,
+    pub lives: u32,   // hits that end the run; 1 is the classic one-touch game
+    pub shields: u32, // hits absorbed before any life is lost
+    pub invulnerable_ticks: u64,
+    pub powerup_probability: f64, // chance per tick of a power-up appearing
+    pub obstacles: bool,          // mix in obstacle kinds other than plain blocks
+    pub hazard_probability: f64,  // chance per tick of a telegraphed hazard
+    pub wave_probability: f64,    // chance per tick of an authored wave starting
+    pub fair: bool,               // keep random rows from trapping the player
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Self {
+            difficulty: Difficulty::default(),
+            movement: Movement::default(),
+            physics: Physics::default(),
+            lives: 1,
+            shields: 0,
+            invulnerable_ticks: INVULNERABLE_TICKS,
+            powerup_probability: POWERUP_PROBABILITY,
+            obstacles: true,
+            hazard_probability: HAZARD_PROBABILITY,
+            wave_probability: WAVE_PROBABILITY,
+            fair: true,
+        }
+    }
+}
+
+impl Rules {
+    // The rules runs used before they were configurable
+    pub fn legacy() -> Self {
+        Self {
+            difficulty: Difficulty::with_curve(Curve::Flat),
+            movement: Movement::Horizontal,
+            physics: Physics::Step,
+            lives: 1,
+            shields: 0,
+            invulnerable_ticks: INVULNERABLE_TICKS,
+            powerup_probability: 0.0,
+            obstacles: false,
+            hazard_probability: 0.0,
+            wave_probability: 0.0,
+            fair: false,
+        }
+    }
+
+    // The rules for a board drawn at the given resolution. Its extra rows go
+    // by in more, shorter ticks, so blocks cross the screen, immunity wears
+    // off and the difficulty ramps at the same pace as in ASCII.
+    pub fn scaled_to(mut self, resolution: Resolution) -> Self {
+        let rows = u64::from(resolution.scale().1);
+        let d = &mut self.difficulty;
+        // Whole milliseconds, as replay headers store them
+        let shorten = |rate: Duration| {
+            let millis = u64::try_from(rate.as_millis()).unwrap_or(u64::MAX);
+            Duration::from_millis(millis.div_ceil(rows).max(1))
+        };
+        d.base_tick_rate = shorten(d.base_tick_rate);
+        d.min_tick_rate = shorten(d.min_tick_rate);
+        d.ramp = d.ramp.saturating_mul(rows);
+        self.invulnerable_ticks = self.invulnerable_ticks.saturating_mul(rows);
+        self
+    }
+
+    // Write the rules as `key value...` lines for a replay header
+    pub fn write<W: Write>(&self, mut w: W) -> io::Result<()> {
+        let d = &self.difficulty;
+        writeln!(w, "curve {}", d.curve)?;
+        writeln!(w, "ramp {}", d.ramp)?;
+        writeln!(
+            w,
+            "spawn {} {}",
+            d.base_spawn_probability, d.max_spawn_probability
+        )?;
+        writeln!(
+            w,
+            "tick_ms {} {}",
+            d.base_tick_rate.as_millis(),
+            d.min_tick_rate.as_millis()
+        )?;
+        writeln!(w, "movement {}", self.movement)?;
+        writeln!(w, "physics {}", self.physics)?;
+        writeln!(
+            w,
+            "lives {} {} {}",
+            self.lives, self.shields, self.invulnerable_ticks
+        )?;
+        writeln!(w, "powerups {}", self.powerup_probability)?;
+        writeln!(
+            w,
+            "obstacles {}",
+            if self.obstacles { "mixed" } else { "plain" }
+        )?;
+        writeln!(w, "hazards {}", self.hazard_probability)?;
+        writeln!(w, "waves {}", self.wave_probability)?;
+        writeln!(w, "spawns {}", if self.fair { "fair" } else { "chaos" })?;
+        Ok(())
+    }
+
+    // Apply one header line written by `write`, returning false if the key
+    // isn't a rule
+    pub fn parse_line(&mut self, fields: &[&str]) -> Result<bool, String> {
+        let d = &mut self.difficulty;
+        match fields {
+            ["curve", curve] => {
+                d.curve = Curve::from_name(curve).ok_or(format!("unknown curve `{curve}`"))?;
+            }
+            ["ramp", ramp] => d.ramp = parse(ramp, "ramp")?,
+            ["spawn", base, max] => {
+                d.base_spawn_probability = parse_probability(base, "spawn probability")?;
+                d.max_spawn_probability = parse_probability(max, "spawn probability")?;
+            }
+            // A zero tick would have a replay run flat out and never yield
+            ["tick_ms", base, min] => {
+                let base: u64 = parse(base, "tick rate")?;
+                let min: u64 = parse(min, "tick rate")?;
+                if base == 0 {
+                    return Err("tick rate must be greater than 0".into());
+                }
+                if min == 0 || min > base {
+                    return Err(format!(
+                        "minimum tick rate must be between 1 and {base}, got {min}"
+                    ));
+                }
+                d.base_tick_rate = Duration::from_millis(base);
+                d.min_tick_rate = Duration::from_millis(min);
+            }
+            ["movement", "horizontal"] => self.movement = Movement::Horizontal,
+            ["movement", "zone", rows] => self.movement = Movement::Zone(parse(rows, "zone rows")?),
+            ["movement", "free"] => self.movement = Movement::Free,
+            ["movement", ..] => return Err("unknown movement".into()),
+            ["physics", name] => {
+                self.physics =
+                    Physics::from_name(name).ok_or(format!("unknown physics `{name}`"))?;
+            }
+            ["lives", lives, shields, ticks] => {
+                self.lives = parse(lives, "lives")?;
+                self.shields = parse(shields, "shields")?;
+                self.invulnerable_ticks = parse(ticks, "invulnerability")?;
+            }
+            ["obstacles", "mixed"] => self.obstacles = true,
+            ["obstacles", "plain"] => self.obstacles = false,
+            ["obstacles", ..] => return Err("unknown obstacle setting".into()),
+            ["spawns", "fair"] => self.fair = true,
+            ["spawns", "chaos"] => self.fair = false,
+            ["spawns", ..] => return Err("unknown spawn mode".into()),
+            ["waves", probability] => {
+                self.wave_probability = parse_probability(probability, "wave probability")?;
+            }
+            ["hazards", probability] => {
+                self.hazard_probability = parse_probability(probability, "hazard probability")?;
+            }
+            ["powerups", probability] => {
+                self.powerup_probability = parse_probability(probability, "power-up probability")?;
+            }
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+}
+
+// A chance between 0 and 1; anything else (NaN included) would make the
+// spawner's `gen_bool` panic
+fn parse_probability(value: &str, name: &str) -> Result<f64, String> {
+    let probability: f64 = parse(value, name)?;
+    if (0.0..=1.0).contains(&probability) {
+        Ok(probability)
+    } else {
+        Err(format!("{name} `{value}` is not between 0 and 1"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finer_resolutions_tick_faster() {
+        let rules = Rules::default().scaled_to(Resolution::HalfBlock);
+        let d = Difficulty::default();
+        assert_eq!(rules.difficulty.base_tick_rate, d.base_tick_rate / 2);
+        assert_eq!(rules.difficulty.ramp, d.ramp * 2);
+        assert_eq!(
+            Rules::default().scaled_to(Resolution::Ascii),
+            Rules::default()
+        );
+    }
+
+    #[test]
+    fn scaled_tick_rates_survive_a_replay_header() {
+        let mut rules = Rules::default();
+        rules.difficulty.base_tick_rate = Duration::from_millis(3);
+        rules.difficulty.min_tick_rate = Duration::from_millis(1);
+        let rules = rules.scaled_to(Resolution::Braille);
+
+        let mut header = Vec::new();
+        rules.write(&mut header).unwrap();
+        let mut read = Rules::legacy();
+        for line in String::from_utf8(header).unwrap().lines() {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            assert!(read.parse_line(&fields).unwrap());
+        }
+        assert_eq!(read, rules);
+    }
+}
@@ -0,0 +1,183 @@
+use std::env;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+// Entries kept per mode and board size
+pub const MAX_ENTRIES: usize = 10;
+
+const HEADER: &str = "# dodge scores v1";
+
+/// A single finished run in the high-score table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScoreEntry {
+    pub name: String,
+    pub score: u64,
+    pub date: u64, // seconds since the Unix epoch
+    pub seed: u64,
+    pub duration: Duration,
+    pub mode: String,
+    pub width: u16,
+    pub height: u16,
+}
+
+impl ScoreEntry {
+    fn same_table(&self, mode: &str, width: u16, height: u16) -> bool {
+        self.mode == mode && self.width == width && self.height == height
+    }
+}
+
+/// Top scores for every mode and board size, stored as a tab-separated file.
+#[derive(Debug, Clone, Default)]
+pub struct HighScores {
+    entries: Vec<ScoreEntry>,
+}
+
+impl HighScores {
+    // $XDG_DATA_HOME/dodge/scores.tsv, falling back to ~/.local/share
+    pub fn default_path() -> Option<PathBuf> {
+        let data_home = env::var_os("XDG_DATA_HOME")
+            .map(PathBuf::from)
+            .filter(|path| path.is_absolute())
+            .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".local/share")))?;
+        Some(data_home.join("dodge").join("scores.tsv"))
+    }
+
+    // A missing file is just an empty table
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(err) => return Err(err),
+        };
+
+        let mut scores = Self::default();
+        for (index, line) in contents.lines().enumerate() {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let entry = parse_entry(line).ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("malformed score entry on line {}", index + 1),
+                )
+            })?;
+            scores.entries.push(entry);
+        }
+        Ok(scores)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let path = path.as_ref();
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        let mut out = Vec::new();
+        writeln!(out, "{HEADER}")?;
+        for e in &self.entries {
+            writeln!(
+                out,
+                "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+                e.mode,
+                e.width,
+                e.height,
+                e.score,
+                e.name,
+                e.date,
+                e.seed,
+                e.duration.as_millis()
+            )?;
+        }
+
+        // Write to a temporary file first so a crash never truncates the table
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, out)?;
+        fs::rename(tmp, path)
+    }
+
+    pub fn entries(&self) -> &[ScoreEntry] {
+        &self.entries
+    }
+
+    // Entries for one mode and board size, best first
+    pub fn table(&self, mode: &str, width: u16, height: u16) -> Vec<&ScoreEntry> {
+        let mut table: Vec<_> = self
+            .entries
+            .iter()
+            .filter(|e| e.same_table(mode, width, height))
+            .collect();
+        table.sort_by(|a, b| b.score.cmp(&a.score).then(a.date.cmp(&b.date)));
+        table
+    }
+
+    // The 1-based rank a score would get, if it makes the table
+    pub fn rank(&self, mode: &str, width: u16, height: u16, score: u64) -> Option<usize> {
+        if score == 0 {
+            return None;
+        }
+        let table = self.table(mode, width, height);
+        let rank = table.iter().take_while(|e| e.score >= score).count() + 1;
+        (rank <= MAX_ENTRIES).then_some(rank)
+    }
+
+    // Add an entry and drop whatever falls off the bottom of its table
+    pub fn insert(&mut self, mut entry: ScoreEntry) {
+        entry.name = sanitize_name(&entry.name);
+        let (mode, width, height) = (entry.mode.clone(), entry.width, entry.height);
+        self.entries.push(entry);
+
+        let table: Vec<ScoreEntry> = self
+            .table(&mode, width, height)
+            .into_iter()
+            .take(MAX_ENTRIES)
+            .cloned()
+            .collect();
+        self.entries.retain(|e| !e.same_table(&mode, width, height));
+        self.entries.extend(table);
+    }
+}
+
+// Names are stored in a tab-separated file, so strip anything that would break it
+fn sanitize_name(name: &str) -> String {
+    let name: String = name.chars().filter(|c| !c.is_control()).collect();
+    let name = name.trim();
+    if name.is_empty() {
+        "anonymous".to_string()
+    } else {
+        name.to_string()
+    }
+}
+
+fn parse_entry(line: &str) -> Option<ScoreEntry> {
+    let fields: Vec<&str> = line.split('\t').collect();
+    let [mode, width, height, score, name, date, seed, duration] = fields.as_slice() else {
+        return None;
+    };
+    Some(ScoreEntry {
+        name: name.to_string(),
+        score: score.parse().ok()?,
+        date: date.parse().ok()?,
+        seed: seed.parse().ok()?,
+        duration: Duration::from_millis(duration.parse().ok()?),
+        mode: mode.to_string(),
+        width: width.parse().ok()?,
+        height: height.parse().ok()?,
+    })
+}
+
+// Format seconds since the Unix epoch as a UTC YYYY-MM-DD date
+pub fn format_date(secs: u64) -> String {
+    // Civil-from-days conversion (Howard Hinnant's algorithm)
+    let days = (secs / 86_400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let doe = days.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!("{year:04}-{month:02}-{day:02}")
+}
@@ -0,0 +1,137 @@
+use std::io::{self, Stdout};
+use std::ops::{Deref, DerefMut};
+use std::panic;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use crossterm::{
+    cursor::Show,
+    event::{
+        DisableMouseCapture, EnableMouseCapture, KeyboardEnhancementFlags,
+        PopKeyboardEnhancementFlags, PushKeyboardEnhancementFlags,
+    },
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use ratatui::{backend::CrosstermBackend, Terminal};
+
+pub type Term = Terminal<CrosstermBackend<Stdout>>;
+
+// Whether keyboard enhancement flags have been pushed and need popping
+static KEYS_ENHANCED: AtomicBool = AtomicBool::new(false);
+
+// Whether mouse events are being captured and capture needs turning off
+static MOUSE_CAPTURED: AtomicBool = AtomicBool::new(false);
+
+// Owns the terminal while it is in raw mode on the alternate screen and puts
+// it back the way it was when dropped, however the program exits
+pub struct TerminalGuard {
+    terminal: Term,
+    interrupted: Arc<AtomicBool>,
+}
+
+impl TerminalGuard {
+    pub fn new() -> io::Result<Self> {
+        // Signals only raise a flag; the game loops notice it and unwind normally
+        let interrupted = Arc::new(AtomicBool::new(false));
+        for &signal in signal_hook::consts::TERM_SIGNALS {
+            signal_hook::flag::register(signal, Arc::clone(&interrupted))?;
+        }
+        #[cfg(unix)]
+        signal_hook::flag::register(signal_hook::consts::SIGHUP, Arc::clone(&interrupted))?;
+
+        install_panic_hook();
+
+        let terminal = Terminal::new(CrosstermBackend::new(io::stdout()))?;
+        enable_raw_mode()?;
+        // From here on dropping the guard undoes whatever was set up
+        let guard = Self {
+            terminal,
+            interrupted,
+        };
+        execute!(io::stdout(), EnterAlternateScreen)?;
+        Ok(guard)
+    }
+
+    // Whether SIGINT, SIGTERM or SIGHUP has been received
+    pub fn interrupted(&self) -> bool {
+        self.interrupted.load(Ordering::Relaxed)
+    }
+
+    // Ask for key repeats and releases as well as presses. Every key is sent
+    // as an escape code so letters report releases too, which also means
+    // shifted letters arrive lowercase; only turn it on while playing.
+    // Terminals without the kitty keyboard protocol ignore the request.
+    pub fn report_key_releases(&mut self, enabled: bool) {
+        if KEYS_ENHANCED.swap(enabled, Ordering::Relaxed) == enabled {
+            return;
+        }
+        // The legacy Windows console refuses; presses still work there
+        let _ = if enabled {
+            execute!(
+                io::stdout(),
+                PushKeyboardEnhancementFlags(
+                    KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES
+                        | KeyboardEnhancementFlags::REPORT_EVENT_TYPES
+                        | KeyboardEnhancementFlags::REPORT_ALL_KEYS_AS_ESCAPE_CODES
+                )
+            )
+        } else {
+            execute!(io::stdout(), PopKeyboardEnhancementFlags)
+        };
+    }
+
+    // Have clicks and pointer movement reported as events
+    pub fn capture_mouse(&mut self, enabled: bool) -> io::Result<()> {
+        if MOUSE_CAPTURED.swap(enabled, Ordering::Relaxed) == enabled {
+            return Ok(());
+        }
+        if enabled {
+            execute!(io::stdout(), EnableMouseCapture)
+        } else {
+            execute!(io::stdout(), DisableMouseCapture)
+        }
+    }
+}
+
+impl Deref for TerminalGuard {
+    type Target = Term;
+
+    fn deref(&self) -> &Term {
+        &self.terminal
+    }
+}
+
+impl DerefMut for TerminalGuard {
+    fn deref_mut(&mut self) -> &mut Term {
+        &mut self.terminal
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        let _ = restore();
+    }
+}
+
+// Leave raw mode and the alternate screen and show the cursor again
+pub fn restore() -> io::Result<()> {
+    if KEYS_ENHANCED.swap(false, Ordering::Relaxed) {
+        let _ = execute!(io::stdout(), PopKeyboardEnhancementFlags);
+    }
+    if MOUSE_CAPTURED.swap(false, Ordering::Relaxed) {
+        let _ = execute!(io::stdout(), DisableMouseCapture);
+    }
+    disable_raw_mode()?;
+    execute!(io::stdout(), LeaveAlternateScreen, Show)
+}
+
+// Restore the terminal before the default hook prints the panic message,
+// otherwise it is lost on the alternate screen
+fn install_panic_hook() {
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        let _ = restore();
+        default_hook(info);
+    }));
+}
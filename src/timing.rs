@@ -0,0 +1,71 @@
+use std::time::{Duration, Instant};
+
+// Ticks run back to back before the backlog is dropped, so a long stall
+// (suspended process, slow terminal) doesn't fast-forward the game
+pub const MAX_CATCH_UP: u32 = 5;
+
+// Accumulates wall-clock time and hands it out as fixed simulation steps
+pub struct FixedTimestep {
+    accumulator: Duration,
+    last: Instant,
+    paused: bool,
+}
+
+impl FixedTimestep {
+    pub fn new() -> Self {
+        Self {
+            accumulator: Duration::ZERO,
+            last: Instant::now(),
+            paused: false,
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    // Time spent paused never reaches the accumulator
+    pub fn set_paused(&mut self, paused: bool) {
+        self.advance();
+        self.paused = paused;
+    }
+
+    // How long until a step of the given length is due, or None while paused
+    pub fn until_next(&self, step: Duration) -> Option<Duration> {
+        if self.paused {
+            return None;
+        }
+        Some(step.saturating_sub(self.accumulator + self.last.elapsed()))
+    }
+
+    // How far (0.0 to 1.0) into a step of the given length the clock is
+    pub fn progress(&self, step: Duration) -> f64 {
+        let mut elapsed = self.accumulator;
+        if !self.paused {
+            elapsed += self.last.elapsed();
+        }
+        (elapsed.as_secs_f64() / step.as_secs_f64().max(f64::EPSILON)).min(1.0)
+    }
+
+    // Add the time elapsed since the last call
+    pub fn advance(&mut self) {
+        let now = Instant::now();
+        if !self.paused {
+            self.accumulator += now - self.last;
+        }
+        self.last = now;
+    }
+
+    // Consume one step if enough time has accumulated
+    pub fn take_step(&mut self, step: Duration) -> bool {
+        if self.paused || self.accumulator < step {
+            return false;
+        }
+        self.accumulator -= step;
+        true
+    }
+
+    pub fn discard_backlog(&mut self) {
+        self.accumulator = Duration::ZERO;
+    }
+}
@@ -0,0 +1,389 @@
+use std::env;
+
+use ratatui::{
+    backend::Backend,
+    layout::{Alignment, Rect},
+    style::{Color, Modifier, Style},
+    text::{Span, Spans},
+    widgets::{Block as WidgetBlock, Borders, Clear, Paragraph},
+    Frame,
+};
+
+use dodge::{BlockKind, Game, HazardKind, HazardPhase, Movement, PowerUp, Resolution};
+
+use crate::config::Visuals;
+
+// Most lives shown as a row of hearts; more are shown as a count
+const MAX_HEARTS: u32 = 10;
+
+// Center a bordered box around a playfield of the given size, clipped to the screen
+pub fn arena_rect(screen: Rect, width: u16, height: u16) -> Rect {
+    let width = width.saturating_add(2).min(screen.width);
+    let height = height.saturating_add(2).min(screen.height);
+    Rect {
+        x: screen.x + (screen.width - width) / 2,
+        y: screen.y + (screen.height - height) / 2,
+        width,
+        height,
+    }
+}
+
+// The bordered box the board is drawn in
+fn board_box(screen: Rect, game: &Game, resolution: Resolution) -> Rect {
+    let (columns, rows) = resolution.screen_size((game.width, game.height));
+    arena_rect(screen, columns, rows)
+}
+
+// The board cell drawn at a terminal position, if it is on the visible part
+// of the board. With several cells per character it picks the middle one.
+pub fn board_cell(
+    screen: Rect,
+    game: &Game,
+    visuals: &Visuals,
+    (column, row): (u16, u16),
+) -> Option<(u16, u16)> {
+    let resolution = visuals.resolution;
+    let inner_area = WidgetBlock::default()
+        .borders(Borders::ALL)
+        .inner(board_box(screen, game, resolution));
+    if !(inner_area.left()..inner_area.right()).contains(&column)
+        || !(inner_area.top()..inner_area.bottom()).contains(&row)
+    {
+        return None;
+    }
+    let (sx, sy) = resolution.scale();
+    let x = (column - inner_area.x) * sx + (sx - 1) / 2;
+    let y = (row - inner_area.y) * sy + (sy - 1) / 2;
+    (x < game.width && y < game.height).then_some((x, y))
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Cell {
+    Empty,
+    Zone, // empty cell the player may move into
+    Block(BlockKind),
+    Item(PowerUp),
+    Warning,       // path of a hazard about to strike
+    WarningMarker, // flashing marker at the edge where it will strike from
+    Strike(HazardKind),
+    Player,
+}
+
+// Plain blocks use the configured glyph; every other kind has its own
+fn block_glyph(kind: BlockKind, visuals: &Visuals) -> char {
+    match kind {
+        BlockKind::Normal => visuals.block_glyph,
+        BlockKind::Fast => '|',
+        BlockKind::Slow => 'o',
+        BlockKind::Bouncer => 'X',
+        BlockKind::ZigZag => 'Z',
+        BlockKind::Slab => '=',
+        BlockKind::Accelerating => 'v',
+    }
+}
+
+// Each power-up gets its own glyph and color so it can be told apart mid-fall
+fn powerup_glyph(kind: PowerUp) -> (char, Color) {
+    match kind {
+        PowerUp::SlowTime => ('T', Color::Cyan),
+        PowerUp::Shield => ('O', Color::LightBlue),
+        PowerUp::Shrink => ('s', Color::Green),
+        PowerUp::Multiplier => ('$', Color::LightYellow),
+        PowerUp::Bomb => ('*', Color::LightRed),
+        PowerUp::Magnet => ('U', Color::Magenta),
+    }
+}
+
+// Half blocks and braille need a UTF-8 terminal; anything else gets ASCII
+pub fn fallback(resolution: Resolution) -> Resolution {
+    let locale = ["LC_ALL", "LC_CTYPE", "LANG"]
+        .iter()
+        .find_map(|name| env::var(name).ok().filter(|value| !value.is_empty()))
+        .unwrap_or_default()
+        .to_lowercase();
+    if locale.contains("utf-8") || locale.contains("utf8") {
+        resolution
+    } else {
+        Resolution::Ascii
+    }
+}
+
+// Draw the game frame `progress` (0.0 to 1.0) of the way through the next
+// tick, so fast movement shows up between updates
+pub fn draw<B: Backend>(
+    f: &mut Frame<B>,
+    game: &Game,
+    visuals: &Visuals,
+    title: &str,
+    progress: f64,
+) {
+    let resolution = visuals.resolution;
+    let outer_area = board_box(f.size(), game, resolution);
+    let block = WidgetBlock::default().borders(Borders::ALL).title(title);
+    let inner_area = block.inner(outer_area);
+
+    // Paint the visible part of the board back to front: zone, hazard
+    // warnings, items, blocks, strikes, then the player
+    let (width, height) = resolution.board_size((inner_area.width, inner_area.height));
+    let (width, height) = (width.min(game.width), height.min(game.height));
+    let mut cells = vec![Cell::Empty; usize::from(width) * usize::from(height)];
+    let mut paint = |x: u16, y: u16, cell: Cell| {
+        if x < width && y < height {
+            cells[usize::from(y) * usize::from(width) + usize::from(x)] = cell;
+        }
+    };
+
+    // Mark out the rows the player can roam when they're only part of the board
+    if let Movement::Zone(_) = game.rules.movement {
+        let (top, bottom) = game.player_rows();
+        for y in top..=bottom {
+            for x in 0..width {
+                paint(x, y, Cell::Zone);
+            }
+        }
+    }
+    // A hazard's path, or the strike itself, covers a whole column or row
+    let paint_line =
+        |paint: &mut dyn FnMut(u16, u16, Cell), kind: HazardKind, cell: Cell| match kind {
+            HazardKind::Column(x) => (0..height).for_each(|y| paint(x, y, cell)),
+            HazardKind::Row(y) => (0..width).for_each(|x| paint(x, y, cell)),
+        };
+    for hazard in game.hazards() {
+        if let HazardPhase::Warning { ticks_left } = hazard.phase {
+            paint_line(&mut paint, hazard.kind, Cell::Warning);
+            // The marker flashes on alternate ticks
+            if ticks_left.is_multiple_of(2) {
+                match hazard.kind {
+                    HazardKind::Column(x) => paint(x, 0, Cell::WarningMarker),
+                    HazardKind::Row(y) => paint(0, y, Cell::WarningMarker),
+                }
+            }
+        }
+    }
+    for item in game.items() {
+        paint(item.x, item.y, Cell::Item(item.kind));
+    }
+    for b in game.blocks() {
+        let y = b.row_at(progress);
+        for x in b.x..b.x.saturating_add(b.width) {
+            paint(x, y, Cell::Block(b.kind));
+        }
+    }
+    for hazard in game.hazards().iter().filter(|h| h.is_striking()) {
+        paint_line(&mut paint, hazard.kind, Cell::Strike(hazard.kind));
+    }
+    // The player blinks while invulnerable after a hit
+    let blink_off = game.invulnerable > 0 && !game.game_over && game.tick.is_multiple_of(2);
+    if !blink_off {
+        let (x, y) = game.player_at(progress);
+        paint(x, y, Cell::Player);
+    }
+
+    // Turn the board into lines of text, merging runs of identical styles
+    // into a single span
+    let cell_at = |x: u16, y: u16| {
+        if x < width && y < height {
+            cells[usize::from(y) * usize::from(width) + usize::from(x)]
+        } else {
+            Cell::Empty
+        }
+    };
+    let (sx, sy) = resolution.scale();
+    let mut lines = Vec::with_capacity(usize::from(inner_area.height));
+    for row in 0..height.div_ceil(sy) {
+        let mut spans = Vec::new();
+        let mut run: Option<(Style, String)> = None;
+        for column in 0..width.div_ceil(sx) {
+            let (glyph, style) = match resolution {
+                Resolution::Ascii => ascii_glyph(cell_at(column, row), visuals),
+                Resolution::HalfBlock => half_block_glyph(
+                    cell_at(column, row * 2),
+                    cell_at(column, row * 2 + 1),
+                    visuals,
+                ),
+                Resolution::Braille => {
+                    let mut group = [Cell::Empty; 8];
+                    for (i, cell) in group.iter_mut().enumerate() {
+                        let (dx, dy) = ((i / 4) as u16, (i % 4) as u16);
+                        *cell = cell_at(column * 2 + dx, row * 4 + dy);
+                    }
+                    braille_glyph(group, visuals)
+                }
+            };
+            match &mut run {
+                Some((run_style, text)) if *run_style == style => text.push(glyph),
+                _ => {
+                    if let Some((style, text)) = run.take() {
+                        spans.push(Span::styled(text, style));
+                    }
+                    run = Some((style, glyph.to_string()));
+                }
+            }
+        }
+        if let Some((style, text)) = run {
+            spans.push(Span::styled(text, style));
+        }
+        lines.push(Spans::from(spans));
+    }
+
+    let paragraph = Paragraph::new(lines)
+        .block(block)
+        .alignment(Alignment::Left);
+    f.render_widget(paragraph, outer_area);
+}
+
+// One board cell per character
+fn ascii_glyph(cell: Cell, visuals: &Visuals) -> (char, Style) {
+    match cell {
+        Cell::Empty => (' ', Style::default()),
+        Cell::Zone => ('.', Style::default().fg(Color::DarkGray)),
+        Cell::Block(kind) => (block_glyph(kind, visuals), visuals.block_style()),
+        Cell::Item(kind) => {
+            let (glyph, color) = powerup_glyph(kind);
+            (
+                glyph,
+                Style::default().fg(color).add_modifier(Modifier::BOLD),
+            )
+        }
+        Cell::Warning => ('·', Style::default().fg(Color::Yellow)),
+        Cell::WarningMarker => (
+            '!',
+            Style::default()
+                .fg(Color::Black)
+                .bg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        ),
+        Cell::Strike(kind) => {
+            let glyph = match kind {
+                HazardKind::Column(_) => '┃',
+                HazardKind::Row(_) => '━',
+            };
+            (
+                glyph,
+                Style::default()
+                    .fg(Color::LightRed)
+                    .add_modifier(Modifier::BOLD),
+            )
+        }
+        // Player drawn with a contrasting style
+        Cell::Player => (visuals.player_glyph, visuals.player_style()),
+    }
+}
+
+// The solid color a cell is drawn in at sub-cell resolutions; None for cells
+// that leave the background showing
+fn cell_color(cell: Cell, visuals: &Visuals) -> Option<Color> {
+    let solid = |style: Style| match style.bg {
+        Some(Color::Reset) | None => style.fg.unwrap_or(Color::Reset),
+        Some(bg) => bg,
+    };
+    match cell {
+        Cell::Empty | Cell::Zone => None,
+        Cell::Block(BlockKind::Normal | BlockKind::Slab) => Some(solid(visuals.block_style())),
+        Cell::Block(kind) => Some(block_color(kind)),
+        Cell::Item(kind) => Some(powerup_glyph(kind).1),
+        Cell::Warning | Cell::WarningMarker => Some(Color::Yellow),
+        Cell::Strike(_) => Some(Color::LightRed),
+        Cell::Player => Some(solid(visuals.player_style())),
+    }
+}
+
+// Without glyphs to tell them apart, moving obstacles get their own colors
+fn block_color(kind: BlockKind) -> Color {
+    match kind {
+        BlockKind::Normal | BlockKind::Slab => Color::Reset,
+        BlockKind::Fast => Color::Blue,
+        BlockKind::Slow => Color::Gray,
+        BlockKind::Bouncer => Color::LightMagenta,
+        BlockKind::ZigZag => Color::LightGreen,
+        BlockKind::Accelerating => Color::Red,
+    }
+}
+
+// Empty zone cells keep their dots so the zone stays visible
+fn zone_glyph() -> (char, Style) {
+    ('.', Style::default().fg(Color::DarkGray))
+}
+
+// Two board rows per character: the top cell in the foreground of ▀ and the
+// bottom one in its background
+fn half_block_glyph(top: Cell, bottom: Cell, visuals: &Visuals) -> (char, Style) {
+    match (cell_color(top, visuals), cell_color(bottom, visuals)) {
+        (None, None) if top == Cell::Zone || bottom == Cell::Zone => zone_glyph(),
+        (None, None) => (' ', Style::default()),
+        (Some(top), None) => ('▀', Style::default().fg(top)),
+        (None, Some(bottom)) => ('▄', Style::default().fg(bottom)),
+        (Some(top), Some(bottom)) if top == bottom => ('█', Style::default().fg(top)),
+        (Some(top), Some(bottom)) => ('▀', Style::default().fg(top).bg(bottom)),
+    }
+}
+
+// Braille dot for each cell of a 2x4 group, listed column by column
+const BRAILLE_DOTS: [u32; 8] = [0x01, 0x02, 0x04, 0x40, 0x08, 0x10, 0x20, 0x80];
+
+// A 2x4 group of board cells as braille dots. A character has one color, so
+// it takes the color of its most important cell; hazard warnings only show
+// in characters with nothing else in them.
+fn braille_glyph(group: [Cell; 8], visuals: &Visuals) -> (char, Style) {
+    let rank = |cell: Cell| match cell {
+        Cell::Empty | Cell::Zone => 0,
+        Cell::Warning | Cell::WarningMarker => 1,
+        Cell::Block(_) => 2,
+        Cell::Item(_) => 3,
+        Cell::Strike(_) => 4,
+        Cell::Player => 5,
+    };
+    let Some(top) = group.iter().copied().max_by_key(|&cell| rank(cell)) else {
+        return (' ', Style::default());
+    };
+    match rank(top) {
+        0 if group.contains(&Cell::Zone) => return zone_glyph(),
+        0 => return (' ', Style::default()),
+        _ => {}
+    }
+    let shown = |cell: Cell| rank(cell) > 1 || rank(top) == 1 && rank(cell) == 1;
+    let dots = group
+        .iter()
+        .zip(BRAILLE_DOTS)
+        .filter(|&(&cell, _)| shown(cell))
+        .fold(0, |dots, (_, dot)| dots | dot);
+    let glyph = char::from_u32(0x2800 + dots).unwrap_or(' ');
+    let color = cell_color(top, visuals).unwrap_or(Color::Reset);
+    (glyph, Style::default().fg(color))
+}
+
+// Score, level, remaining lives when the run has more than one, shields and
+// active power-ups shown in the border
+pub fn status_title(game: &Game) -> String {
+    let mut title = format!("Score: {}  Level: {}", game.score, game.level());
+    let rules = &game.rules;
+    if rules.lives > MAX_HEARTS {
+        title.push_str(&format!("  ♥×{}", game.lives));
+    } else if rules.lives > 1 {
+        let lost = rules.lives.saturating_sub(game.lives) as usize;
+        title.push_str("  ");
+        title.push_str(&"♥".repeat(game.lives as usize));
+        title.push_str(&"♡".repeat(lost));
+    }
+    if game.shields > 0 {
+        title.push_str(&format!("  +{} shield", game.shields));
+    }
+    for (kind, ticks) in game.active_effects() {
+        title.push_str(&format!("  [{kind} {ticks}]"));
+    }
+    if game.dash_cooldown() > 0 {
+        title.push_str(&format!("  [dash {}]", game.dash_cooldown()));
+    }
+    title
+}
+
+// Draw a bordered message box centered over the screen
+pub fn draw_popup<B: Backend>(f: &mut Frame<B>, title: &str, lines: Vec<Spans>) {
+    let width = lines.iter().map(Spans::width).max().unwrap_or(0) as u16;
+    let area = arena_rect(f.size(), width.max(title.len() as u16), lines.len() as u16);
+    let paragraph = Paragraph::new(lines)
+        .block(WidgetBlock::default().borders(Borders::ALL).title(title))
+        .alignment(Alignment::Center);
+    f.render_widget(Clear, area);
+    f.render_widget(paragraph, area);
+}